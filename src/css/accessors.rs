@@ -0,0 +1,98 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use quote::{Ident, Tokens};
+
+use errors::Error;
+
+use css::imports::Loaded;
+use css::scope::{is_field_name, ScopedClass};
+use css::selectors;
+use css::types::*;
+
+// Other methods of the struct wrapping the stylesheet, which classes can't
+// be named after.
+const RESERVED: [&str; 7] = [
+    "get_for_element",
+    "get_for_state",
+    "get_for_theme",
+    "get_for_viewport",
+    "into_stylesheet",
+    "new",
+    "stylesheet"
+];
+
+// A method looking up the declarations of a class.
+pub struct Accessor {
+    pub method: String,
+    pub class: String
+}
+
+// Finds the classes which rules apply to on their own, e.g. `.foo` but not
+// `.foo > .bar`, each of which gets an accessor.
+pub fn collect(loaded: &[Loaded]) -> (Vec<Accessor>, Vec<Error>) {
+    let mut accessors: Vec<Accessor> = vec![];
+    let mut errors = vec![];
+
+    for loaded in loaded {
+        for rule in &loaded.sheet.rules {
+            let rule = match *rule {
+                Rule::Style(ref rule) => rule,
+                Rule::At(_) => continue
+            };
+
+            for selector in selectors::split(&rule.selectors) {
+                let class = match selectors::single_class(&selectors::serialize(&selector)) {
+                    Some(class) => class,
+                    None => continue
+                };
+                if accessors.iter().any(|accessor| accessor.class == class) {
+                    continue;
+                }
+
+                let method = class.replace('-', "_");
+                let message = if !is_field_name(&method) || RESERVED.contains(&method.as_str()) {
+                    Some(format!("class `{}` can't be used as a method name", class))
+                } else {
+                    accessors
+                        .iter()
+                        .find(|accessor| accessor.method == method)
+                        .map(|accessor| format!("classes `{}` and `{}` would both be the method `{}`", accessor.class, class, method))
+                };
+                match message {
+                    Some(message) => errors.push(loaded.source.error(rule.offset, message)),
+                    None => accessors.push(Accessor { method, class })
+                }
+            }
+        }
+    }
+
+    (accessors, errors)
+}
+
+// A method per class, so that looking up a class which doesn't exist fails
+// to compile, and classes which are never looked up are reported as dead
+// code. Scoped classes are looked up by their new name.
+pub fn methods(accessors: &[Accessor], classes: Option<&Vec<ScopedClass>>) -> Vec<Tokens> {
+    accessors
+        .iter()
+        .map(|accessor| {
+            let method = Ident::new(accessor.method.as_str());
+            let scoped = classes.and_then(|classes| classes.iter().find(|class| class.name == accessor.class));
+            let selector = format!(".{}", scoped.map_or(&accessor.class, |class| &class.scoped));
+            quote! {
+                pub fn #method(&mut self) -> StyleDeclarations {
+                    self.0.get(#selector)
+                }
+            }
+        })
+        .collect()
+}
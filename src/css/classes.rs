@@ -0,0 +1,119 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::path::PathBuf;
+use std::result;
+
+use proc_macro::Span;
+
+use errors::Result;
+use files::Root;
+use source::Source;
+
+use css::imports;
+use css::selectors;
+use css::types::*;
+use css::variables;
+
+// The rules of a stylesheet file which apply to a single class, so that the
+// `class` attributes in `rsx!` can be resolved at compile-time.
+pub struct Classes {
+    name: String,
+    files: Vec<PathBuf>,
+    rules: Vec<ClassRule>
+}
+
+struct ClassRule {
+    classes: Vec<String>,
+    declarations: String
+}
+
+impl Classes {
+    // Only syntax errors are reported here, anything else is left to the
+    // `css!` invocation which presumably loads the same file.
+    pub fn load(path: &str, span: Span, root: Root) -> Result<Self> {
+        let source = Source::from_file(root.resolve(path, span)?, span)?;
+        let (mut loaded, mut errors) = imports::load(source, root);
+        errors.extend(variables::substitute(&mut loaded));
+
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error);
+        }
+
+        let rules = loaded
+            .iter()
+            .flat_map(|loaded| loaded.sheet.rules.iter())
+            .filter_map(|rule| match *rule {
+                Rule::Style(ref rule) => class_rule(rule),
+                Rule::At(_) => None
+            })
+            .collect();
+
+        Ok(Classes {
+            name: path.to_string(),
+            files: loaded.iter().filter_map(|loaded| loaded.source.path()).map(PathBuf::from).collect(),
+            rules
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    // The declarations of all rules applying to the given classes, in source
+    // order. Rules all have the same specificity, which makes that the order
+    // they apply in. Fails with the first class which isn't defined.
+    pub fn declarations(&self, names: &[&str]) -> result::Result<String, String> {
+        if let Some(name) = names.iter().find(|name| !self.rules.iter().any(|rule| rule.defines(name))) {
+            return Err(name.to_string());
+        }
+
+        let declarations: Vec<&str> = self.rules
+            .iter()
+            .filter(|rule| names.iter().any(|name| rule.defines(name)))
+            .map(|rule| rule.declarations.as_str())
+            .collect();
+
+        Ok(declarations.join(" "))
+    }
+}
+
+impl ClassRule {
+    fn defines(&self, name: &str) -> bool {
+        self.classes.iter().any(|class| class == name)
+    }
+}
+
+fn class_rule(rule: &StyleRule) -> Option<ClassRule> {
+    let classes: Vec<String> = selectors::split(&rule.selectors)
+        .iter()
+        .filter_map(|selector| selectors::single_class(&selectors::serialize(selector)))
+        .collect();
+
+    if classes.is_empty() {
+        return None;
+    }
+
+    let declarations: Vec<String> = rule.declarations
+        .iter()
+        .map(|declaration| format!("{}: {};", declaration.name, declaration.value))
+        .collect();
+
+    Some(ClassRule {
+        classes,
+        declarations: declarations.join(" ")
+    })
+}
+
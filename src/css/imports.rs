@@ -0,0 +1,210 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use errors::Error;
+use files::{self, Root};
+use source::Source;
+
+use css::parse;
+use css::types::*;
+
+// A stylesheet which is part of the expansion, either the one given to the
+// macro or one it imports, with the edits to make to its text.
+pub struct Loaded {
+    pub source: Source,
+    pub sheet: Sheet,
+    pub edits: Vec<Edit>
+}
+
+// Parses the given stylesheet along with everything it imports, recursively.
+// Imported stylesheets come before the ones importing them, which is the
+// order their rules end up in. A stylesheet imported more than once is only
+// loaded the first time.
+pub fn load(source: Source, root: Root) -> (Vec<Loaded>, Vec<Error>) {
+    let mut loader = Loader {
+        root,
+        loaded: vec![],
+        visited: vec![],
+        errors: vec![]
+    };
+
+    loader.load(source, &mut vec![]);
+    (loader.loaded, loader.errors)
+}
+
+// Joins the edited text of all loaded stylesheets.
+pub fn merge(loaded: &[Loaded]) -> String {
+    let mut text = String::new();
+
+    for loaded in loaded {
+        text.push_str(&edited(loaded, 0, loaded.source.text.len()));
+        text.push('\n');
+    }
+
+    text
+}
+
+// The edited text of part of a stylesheet. Only edits entirely within the
+// given range are made.
+pub fn edited(loaded: &Loaded, start: usize, end: usize) -> String {
+    let mut edits: Vec<&Edit> = loaded.edits.iter().filter(|edit| edit.start >= start && edit.end <= end).collect();
+    edits.sort_by_key(|edit| edit.start);
+
+    let mut text = String::new();
+    let mut position = start;
+    for edit in edits {
+        // Edits inside text which was already replaced have no effect.
+        if edit.start < position {
+            continue;
+        }
+        text.push_str(&loaded.source.text[position..edit.start]);
+        text.push_str(&edit.text);
+        position = edit.end;
+    }
+    text.push_str(&loaded.source.text[position..end]);
+
+    text
+}
+
+struct Loader {
+    root: Root,
+    loaded: Vec<Loaded>,
+    visited: Vec<PathBuf>,
+    errors: Vec<Error>
+}
+
+impl Loader {
+    // The chain holds the files currently being loaded, outermost first.
+    fn load(&mut self, source: Source, chain: &mut Vec<PathBuf>) {
+        let file = source.path().map(canonical);
+        if let Some(ref file) = file {
+            self.visited.push(file.clone());
+            chain.push(file.clone());
+        }
+
+        let (sheet, diagnostics) = parse::parse(&source.text);
+        for diagnostic in diagnostics {
+            self.errors.push(source.error(diagnostic.offset, diagnostic.message));
+        }
+
+        let mut edits = vec![];
+        let mut after_rules = false;
+        for rule in &sheet.rules {
+            let import = match *rule {
+                Rule::At(ref rule) if rule.name == "import" => rule,
+                _ => {
+                    after_rules = true;
+                    continue;
+                }
+            };
+            if after_rules {
+                self.errors.push(source.error(import.offset, "`@import` rules must come before all other rules"));
+                continue;
+            }
+            edits.push(Edit {
+                start: import.offset,
+                end: import.end,
+                text: String::new()
+            });
+            match import_path(&import.prelude) {
+                Some(path) => self.import(&source, import.offset, &path, chain),
+                None => self.errors.push(source.error(
+                    import.offset,
+                    "expected a file path after `@import`, conditional imports are not supported"
+                ))
+            }
+        }
+
+        if file.is_some() {
+            chain.pop();
+        }
+
+        self.loaded.push(Loaded {
+            source,
+            sheet,
+            edits
+        });
+    }
+
+    fn import(&mut self, importer: &Source, offset: usize, path: &str, chain: &mut Vec<PathBuf>) {
+        // Imports are relative to the importing file, or for inline styles,
+        // resolved like a path given to the macro itself.
+        let path = match importer.path() {
+            Some(importer) => importer.parent().unwrap_or_else(|| Path::new("")).join(path),
+            None => match self.root.resolve(path, importer.span(offset)) {
+                Ok(path) => path,
+                Err(error) => return self.errors.push(error)
+            }
+        };
+
+        let file = canonical(&path);
+        if let Some(index) = chain.iter().position(|parent| *parent == file) {
+            let cycle = chain[index..]
+                .iter()
+                .chain(Some(&file))
+                .map(|file| files::display(file))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return self.errors.push(importer.error(offset, format!("import cycle: {}", cycle)));
+        }
+        if self.visited.contains(&file) {
+            return;
+        }
+
+        match Source::from_file(path, importer.span(offset)) {
+            Ok(source) => self.load(source, chain),
+            Err(error) => {
+                let mut names: Vec<String> = chain.iter().map(|file| files::display(file)).collect();
+                names.push(files::display(&file));
+                let message = format!("{} (imported through {})", error.message, names.join(" -> "));
+                self.errors.push(importer.error(offset, message));
+            }
+        }
+    }
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// The file named by an `@import` prelude, either as a string or as a `url()`.
+fn import_path(prelude: &str) -> Option<String> {
+    let prelude = prelude.trim();
+
+    if prelude.starts_with("url(") && prelude.ends_with(')') {
+        let url = prelude[4..prelude.len() - 1].trim();
+        return unquote(url).or_else(|| {
+            if url.is_empty() || url.contains(char::is_whitespace) {
+                None
+            } else {
+                Some(url.to_string())
+            }
+        });
+    }
+
+    unquote(prelude)
+}
+
+fn unquote(text: &str) -> Option<String> {
+    let quote = text.chars().next()?;
+    if text.len() < 2 || (quote != '"' && quote != '\'') || !text.ends_with(quote) {
+        return None;
+    }
+
+    let inner = &text[1..text.len() - 1];
+    if inner.is_empty() || inner.contains(quote) {
+        return None;
+    }
+    Some(inner.to_string())
+}
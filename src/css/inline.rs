@@ -0,0 +1,74 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use proc_macro::Span;
+use quote::{ToTokens, Tokens};
+use rsx_stylesheet::types::Stylesheet;
+
+use errors::{Error, Result};
+
+use css::parse::parse_declaration_list;
+use css::stylesheet::{parse_declaration_block, DECLARATIONS_SELECTOR};
+use css::validate::validate_declarations;
+use css::variables;
+
+// A list of declarations, such as a `style="..."` attribute, parsed at
+// compile-time.
+#[derive(Debug)]
+pub struct InlineStyle {
+    css: String,
+    stylesheet: Stylesheet
+}
+
+impl InlineStyle {
+    // Declarations which can't be represented are silently dropped.
+    pub fn new(css: String) -> Self {
+        InlineStyle {
+            stylesheet: parse_declaration_block(&css),
+            css
+        }
+    }
+
+    pub fn css(&self) -> &str {
+        &self.css
+    }
+}
+
+// Unlike `css!`, declarations which can't be represented are always errors,
+// reported at the attribute's literal.
+pub fn parse_style(css: &str, span: Span) -> Result<InlineStyle> {
+    let (declarations, mut diagnostics) = parse_declaration_list(css);
+
+    for declaration in &declarations {
+        if variables::is_custom(&declaration.name) || variables::has_references(&declaration.value) {
+            return Err(Error::new(span, "custom properties can't be used in `style` attributes"));
+        }
+    }
+    validate_declarations(&declarations, &mut diagnostics);
+
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(Error::new(span, diagnostic.message)),
+        None => Ok(InlineStyle::new(css.to_string()))
+    }
+}
+
+impl ToTokens for InlineStyle {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        let stylesheet = &self.stylesheet;
+        let selector = DECLARATIONS_SELECTOR;
+
+        let declarations = quote! {
+            (#stylesheet).get(#selector)
+        };
+
+        declarations.to_tokens(tokens);
+    }
+}
@@ -0,0 +1,347 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::mem;
+
+use quote::{Ident, ToTokens, Tokens};
+
+use errors::Error;
+use source::{Group, Source};
+use tokens::placeholder;
+
+use css::imports::Loaded;
+use css::selectors;
+use css::stylesheet::{parse_declaration_block, DECLARATIONS_SELECTOR};
+use css::types::*;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Length,
+    Number,
+    Color
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Layout,
+    Theme
+}
+
+// Properties whose value may be a `{expression}`, with the kind of value
+// they take and the styles they belong to.
+const PROPERTIES: [(&str, Kind, Style); 26] = [
+    ("background-color", Kind::Color, Style::Theme),
+    ("bottom", Kind::Length, Style::Layout),
+    ("color", Kind::Color, Style::Theme),
+    ("flex-basis", Kind::Length, Style::Layout),
+    ("flex-grow", Kind::Number, Style::Layout),
+    ("flex-shrink", Kind::Number, Style::Layout),
+    ("height", Kind::Length, Style::Layout),
+    ("left", Kind::Length, Style::Layout),
+    ("margin", Kind::Length, Style::Layout),
+    ("margin-bottom", Kind::Length, Style::Layout),
+    ("margin-left", Kind::Length, Style::Layout),
+    ("margin-right", Kind::Length, Style::Layout),
+    ("margin-top", Kind::Length, Style::Layout),
+    ("max-height", Kind::Length, Style::Layout),
+    ("max-width", Kind::Length, Style::Layout),
+    ("min-height", Kind::Length, Style::Layout),
+    ("min-width", Kind::Length, Style::Layout),
+    ("opacity", Kind::Number, Style::Theme),
+    ("padding", Kind::Length, Style::Layout),
+    ("padding-bottom", Kind::Length, Style::Layout),
+    ("padding-left", Kind::Length, Style::Layout),
+    ("padding-right", Kind::Length, Style::Layout),
+    ("padding-top", Kind::Length, Style::Layout),
+    ("right", Kind::Length, Style::Layout),
+    ("top", Kind::Length, Style::Layout),
+    ("width", Kind::Length, Style::Layout)
+];
+
+// Shorthands which can be interpolated, setting all four sides to one value.
+const SHORTHANDS: [&str; 2] = ["margin", "padding"];
+
+fn property(name: &str) -> Option<(Kind, Style)> {
+    PROPERTIES
+        .iter()
+        .find(|&&(property, _, _)| property == name)
+        .map(|&(_, kind, style)| (kind, style))
+}
+
+// Moves declarations whose value is a `{expression}` out of the static text
+// and into their rule's `interpolated` list.
+pub fn extract(loaded: &mut [Loaded]) -> Vec<Error> {
+    let mut errors = vec![];
+
+    for loaded in loaded.iter_mut() {
+        if !loaded.source.groups().is_empty() {
+            extract_rules(&mut loaded.sheet.rules, &loaded.source, &mut loaded.edits, &mut errors);
+        }
+    }
+
+    errors
+}
+
+// Declarations are interpolated in rules nested in at-rules too, e.g. `@media`.
+fn extract_rules(rules: &mut [Rule], source: &Source, edits: &mut Vec<Edit>, errors: &mut Vec<Error>) {
+    for rule in rules {
+        let rule = match *rule {
+            Rule::Style(ref mut rule) => rule,
+            Rule::At(AtRule {
+                block: Some(AtRuleBlock::Rules(ref mut rules)),
+                ..
+            }) => {
+                extract_rules(rules, source, edits, errors);
+                continue;
+            }
+            Rule::At(_) => continue
+        };
+
+        for declaration in mem::replace(&mut rule.declarations, vec![]) {
+            let inside: Vec<&Group> = source
+                .groups()
+                .iter()
+                .filter(|group| group.start >= declaration.value_offset && group.end <= declaration.end)
+                .collect();
+            if inside.is_empty() {
+                rule.declarations.push(declaration);
+                continue;
+            }
+
+            match interpolate(&declaration, &inside, &source.text) {
+                Ok(interpolated) => {
+                    edits.push(Edit {
+                        start: declaration.offset,
+                        end: semicolon_end(&source.text, declaration.end),
+                        text: String::new()
+                    });
+                    rule.interpolated.push(interpolated);
+                }
+                Err(message) => errors.push(source.error(declaration.offset, message))
+            }
+        }
+    }
+}
+
+// The end of a declaration along with the `;` after it, if any.
+fn semicolon_end(text: &str, end: usize) -> usize {
+    let rest = &text[end..];
+    let trimmed = rest.trim_left();
+    if trimmed.starts_with(';') {
+        end + (rest.len() - trimmed.len()) + 1
+    } else {
+        end
+    }
+}
+
+fn interpolate(declaration: &Declaration, groups: &[&Group], text: &str) -> Result<Interpolated, String> {
+    let name = &declaration.name;
+    let (kind, _) = property(name).ok_or_else(|| format!("the value of `{}` can't be interpolated", name))?;
+
+    let group = groups[0];
+    if groups.len() > 1 || group.start != declaration.value_offset {
+        return Err(format!("expected a single `{{expression}}` as the value of `{}`, optionally followed by a unit", name));
+    }
+
+    let unit = match text[group.end..declaration.end].trim() {
+        "" => Unit::None,
+        "px" => Unit::Px,
+        "%" => Unit::Percent,
+        other => return Err(format!("unsupported unit `{}` for `{}`", other, name))
+    };
+    if unit != Unit::None && kind != Kind::Length {
+        return Err(format!("`{}` doesn't take a unit", name));
+    }
+
+    if group.stream.is_empty() {
+        return Err("expected an expression inside `{}`".to_string());
+    }
+
+    Ok(Interpolated {
+        name: name.clone(),
+        expression: group.stream.clone(),
+        unit,
+        offset: declaration.offset
+    })
+}
+
+pub fn has_interpolations(loaded: &[Loaded]) -> bool {
+    loaded.iter().any(|loaded| is_interpolated(&loaded.sheet))
+}
+
+pub fn is_interpolated(sheet: &Sheet) -> bool {
+    sheet.rules.iter().any(|rule| match *rule {
+        Rule::Style(ref rule) => !rule.interpolated.is_empty(),
+        Rule::At(_) => false
+    })
+}
+
+// Builds the stylesheet at run time, rule by rule. Static declarations are
+// still parsed at compile-time, and interpolated ones are pushed among them.
+pub fn stylesheet(loaded: &[Loaded]) -> Tokens {
+    rules_stylesheet(loaded.iter().flat_map(|loaded| loaded.sheet.rules.iter()))
+}
+
+// Same as `stylesheet`, for the rules of an `@media` or `@theme` rule.
+pub fn sheet_stylesheet(sheet: &Sheet) -> Tokens {
+    rules_stylesheet(sheet.rules.iter())
+}
+
+fn rules_stylesheet<'a, I>(rules: I) -> Tokens
+where
+    I: Iterator<Item = &'a Rule>
+{
+    let rules: Vec<&StyleRule> = rules
+        .filter_map(|rule| match *rule {
+            Rule::Style(ref rule) => Some(rule),
+            Rule::At(_) => None
+        })
+        .collect();
+
+    quote! {
+        Stylesheet::from(InlineRules::from_vec(vec![#(#rules),*]))
+    }
+}
+
+impl ToTokens for StyleRule {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        let selectors: Vec<Tokens> = selectors::split(&self.selectors)
+            .iter()
+            .map(|selector| {
+                let css_string = selectors::serialize(selector);
+                let specificity = selectors::specificity(&css_string);
+                quote! {
+                    StyleSelector {
+                        css_string: #css_string.into(),
+                        specificity: #specificity
+                    }
+                }
+            })
+            .collect();
+
+        let declarations = if self.interpolated.is_empty() {
+            static_declarations(&self.declarations)
+        } else {
+            // Expressions are all evaluated before anything else is bound,
+            // so that they can't refer to the locals used here by accident.
+            let values: Vec<Ident> = (0..self.interpolated.len()).map(|index| Ident::new(format!("value_{}", index))).collect();
+            let expressions: Vec<Tokens> = self.interpolated
+                .iter()
+                .map(|interpolated| placeholder(interpolated.expression.clone()))
+                .collect();
+            let parts = in_source_order(&self.declarations, &self.interpolated, &values);
+            quote! {
+                {
+                    let (#(#values,)*) = (#(#expressions,)*);
+                    let mut declarations = StyleDeclarations(InlineDeclarations::default());
+                    #(#parts;)*
+                    declarations
+                }
+            }
+        };
+
+        let rule = quote! {
+            StyleRule {
+                selectors: StyleSelectors(InlineSelectors::from_vec(vec![#(#selectors),*])),
+                declarations: #declarations
+            }
+        };
+
+        rule.to_tokens(tokens);
+    }
+}
+
+fn static_declarations(declarations: &[Declaration]) -> Tokens {
+    if declarations.is_empty() {
+        return quote! {
+            StyleDeclarations(InlineDeclarations::default())
+        };
+    }
+
+    let css: Vec<String> = declarations
+        .iter()
+        .map(|declaration| format!("{}: {};", declaration.name, declaration.value))
+        .collect();
+    let stylesheet = parse_declaration_block(&css.join(" "));
+    let selector = DECLARATIONS_SELECTOR;
+    quote! {
+        (#stylesheet).get(#selector)
+    }
+}
+
+// Static declarations are parsed in runs between interpolated ones, so that
+// later declarations still override earlier ones, as in the source.
+fn in_source_order(declarations: &[Declaration], interpolated: &[Interpolated], values: &[Ident]) -> Vec<Tokens> {
+    let mut parts = vec![];
+    let mut rest = declarations;
+
+    for (interpolated, value) in interpolated.iter().zip(values) {
+        let before = rest.iter().take_while(|declaration| declaration.offset < interpolated.offset).count();
+        if before > 0 {
+            let run = static_declarations(&rest[..before]);
+            parts.push(quote! { declarations.0.extend(#run.0) });
+        }
+        rest = &rest[before..];
+        parts.extend(pushes(interpolated, value));
+    }
+
+    if !rest.is_empty() {
+        let run = static_declarations(rest);
+        parts.push(quote! { declarations.0.extend(#run.0) });
+    }
+
+    parts
+}
+
+fn pushes(interpolated: &Interpolated, value: &Ident) -> Vec<Tokens> {
+    let name = interpolated.name.as_str();
+    let (kind, style) = property(name).unwrap();
+
+    let converted = match (kind, interpolated.unit) {
+        (_, Unit::Px) => quote! { StyleUnit::Point((#value as f32).into()) },
+        (_, Unit::Percent) => quote! { StyleUnit::Percent((#value as f32).into()) },
+        (Kind::Number, Unit::None) => quote! { (#value as f32).into() },
+        (_, Unit::None) => quote! { #value.clone().into() }
+    };
+
+    let longhands = if SHORTHANDS.contains(&name) {
+        ["top", "right", "bottom", "left"].iter().map(|side| format!("{}-{}", name, side)).collect()
+    } else {
+        vec![name.to_string()]
+    };
+
+    longhands
+        .iter()
+        .map(|longhand| {
+            let variant = Ident::new(camel_case(longhand));
+            match style {
+                Style::Layout => quote! {
+                    declarations.0.push(StyleDeclaration::Layout(FlexStyle::#variant(#converted)))
+                },
+                Style::Theme => quote! {
+                    declarations.0.push(StyleDeclaration::Theme(ThemeStyle::#variant(#converted)))
+                }
+            }
+        })
+        .collect()
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new()
+            }
+        })
+        .collect()
+}
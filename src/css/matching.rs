@@ -0,0 +1,210 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use quote::Tokens;
+
+use css::imports::Loaded;
+use css::parse::is_ident_char;
+use css::selectors;
+use css::types::*;
+
+// A selector made only of type, id and class selectors joined with
+// descendant or child combinators, which can be matched against the
+// ancestry of an element.
+pub struct MatchedRule {
+    pub selector: String,
+    pub specificity: u32,
+    // From the element itself outwards.
+    pub compounds: Vec<Compound>
+}
+
+pub struct Compound {
+    pub tag: String,
+    pub id: String,
+    pub classes: Vec<String>,
+    // How this compound relates to the next one outwards, either " " for a
+    // descendant, ">" for a child, or nothing for the outermost one.
+    pub combinator: &'static str
+}
+
+// Selectors with sibling combinators, attributes, pseudo-classes or
+// pseudo-elements depend on more than an element's ancestry, so they're
+// left out.
+pub fn collect(loaded: &[Loaded]) -> Vec<MatchedRule> {
+    let mut rules = vec![];
+
+    for loaded in loaded {
+        for rule in &loaded.sheet.rules {
+            let rule = match *rule {
+                Rule::Style(ref rule) => rule,
+                Rule::At(_) => continue
+            };
+            for selector in selectors::split(&rule.selectors) {
+                let selector = selectors::serialize(&selector);
+                if let Some(compounds) = parse_complex(&selector) {
+                    let specificity = selectors::specificity(&selector);
+                    rules.push(MatchedRule {
+                        selector,
+                        specificity,
+                        compounds
+                    });
+                }
+            }
+        }
+    }
+
+    rules
+}
+
+// Looks up the declarations of every rule matching an element, given as its
+// ancestry: compound selectors like `view#main.card`, outermost first and
+// ending with the element itself. Rules are applied by specificity, then in
+// source order.
+pub fn method(rules: &[MatchedRule]) -> Tokens {
+    let count = rules.len();
+    let entries: Vec<Tokens> = rules
+        .iter()
+        .map(|rule| {
+            let selector = rule.selector.as_str();
+            let specificity = rule.specificity;
+            let compounds: Vec<Tokens> = rule.compounds
+                .iter()
+                .map(|compound| {
+                    let tag = compound.tag.as_str();
+                    let id = compound.id.as_str();
+                    let classes = &compound.classes;
+                    let combinator = compound.combinator;
+                    quote! { (#tag, #id, &[#(#classes),*], #combinator) }
+                })
+                .collect();
+            quote! { (#selector, #specificity, &[#(#compounds),*]) }
+        })
+        .collect();
+
+    quote! {
+        #[allow(dead_code)]
+        pub fn get_for_element(&mut self, ancestry: &[&str]) -> StyleDeclarations {
+            type Compound = (&'static str, &'static str, &'static [&'static str], &'static str);
+            const RULES: [(&str, u32, &[Compound]); #count] = [#(#entries),*];
+
+            fn matches_compound(&(tag, id, classes, _): &Compound, element: &str) -> bool {
+                let mut names = vec![];
+                let mut prefix = ' ';
+                let mut start = 0;
+                for (index, c) in element.char_indices().chain(Some((element.len(), '.'))) {
+                    if c == '.' || c == '#' {
+                        names.push((prefix, &element[start..index]));
+                        prefix = c;
+                        start = index + 1;
+                    }
+                }
+                (tag.is_empty() || names.contains(&(' ', tag)))
+                    && (id.is_empty() || names.contains(&('#', id)))
+                    && classes.iter().all(|class| names.contains(&('.', *class)))
+            }
+
+            fn matches(compounds: &[Compound], ancestry: &[&str]) -> bool {
+                let (compound, outer) = match compounds.split_first() {
+                    Some(split) => split,
+                    None => return true
+                };
+                let (element, ancestors) = match ancestry.split_last() {
+                    Some(split) => split,
+                    None => return false
+                };
+                if !matches_compound(compound, element) {
+                    return false;
+                }
+                match compound.3 {
+                    ">" => matches(outer, ancestors),
+                    " " => (0..ancestors.len()).rev().any(|end| matches(outer, &ancestors[..end + 1])),
+                    _ => true
+                }
+            }
+
+            let mut matched: Vec<(u32, &str)> = RULES
+                .iter()
+                .filter(|&&(_, _, compounds)| matches(compounds, ancestry))
+                .map(|&(selector, specificity, _)| (specificity, selector))
+                .collect();
+            matched.sort_by_key(|&(specificity, _)| specificity);
+
+            let mut declarations = StyleDeclarations(InlineDeclarations::default());
+            for (_, selector) in matched {
+                declarations.0.extend(self.0.get(selector).0);
+            }
+            declarations
+        }
+    }
+}
+
+// Parses a serialized selector into its compounds, from the last one
+// backwards, if it can be matched against an ancestry. Each compound is
+// recorded with the combinator before it.
+fn parse_complex(selector: &str) -> Option<Vec<Compound>> {
+    let mut compounds = vec![];
+    let mut combinator = "";
+
+    for part in selector.split(' ').filter(|part| !part.is_empty()) {
+        match part {
+            ">" if !compounds.is_empty() && combinator == " " => combinator = ">",
+            _ => {
+                let mut compound = parse_compound(part)?;
+                compound.combinator = combinator;
+                compounds.push(compound);
+                combinator = " ";
+            }
+        }
+    }
+
+    if compounds.is_empty() || combinator != " " {
+        return None;
+    }
+    compounds.reverse();
+    Some(compounds)
+}
+
+// Parses a compound selector like `view#main.card`, where `*` matches any
+// type of element.
+fn parse_compound(text: &str) -> Option<Compound> {
+    let mut compound = Compound {
+        tag: String::new(),
+        id: String::new(),
+        classes: vec![],
+        combinator: ""
+    };
+    let mut rest = text;
+
+    if rest.starts_with('*') {
+        rest = &rest[1..];
+    } else {
+        let end = rest.find(|c| !is_ident_char(c)).unwrap_or_else(|| rest.len());
+        compound.tag = rest[..end].to_lowercase();
+        rest = &rest[end..];
+    }
+
+    while !rest.is_empty() {
+        let prefix = rest.chars().next()?;
+        let end = rest[1..].find(|c| !is_ident_char(c)).map_or(rest.len(), |end| end + 1);
+        let name = rest[1..end].to_string();
+        if name.is_empty() {
+            return None;
+        }
+        match prefix {
+            '.' => compound.classes.push(name),
+            '#' if compound.id.is_empty() => compound.id = name,
+            _ => return None
+        }
+        rest = &rest[end..];
+    }
+
+    Some(compound)
+}
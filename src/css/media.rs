@@ -0,0 +1,431 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::mem;
+use std::result;
+
+use quote::{ToTokens, Tokens};
+
+use errors::Error;
+
+use css::imports::{self, Loaded};
+use css::parse::is_ident_char;
+use css::selectors;
+use css::types::*;
+
+// Pixels per inch, as CSS defines it.
+const DPI: f32 = 96.0;
+
+// The rules of an `@media` block, as text for Servo, along with the
+// condition for them to apply. The outline of the rules is kept for
+// validation, with the index of the loaded stylesheet they're from.
+pub struct MediaRule {
+    pub condition: Condition,
+    pub css: String,
+    pub sheet: Sheet,
+    pub loaded: usize,
+    pub offset: usize
+}
+
+// A media query list, evaluated at run time against a viewport. Only what
+// can be known about a viewport is supported: its size, pixel ratio and
+// color scheme.
+#[derive(Debug, PartialEq)]
+pub enum Condition {
+    Always,
+    Never,
+    Any(Vec<Condition>),
+    All(Vec<Condition>),
+    Not(Box<Condition>),
+    AtLeast(Variable, f32),
+    AtMost(Variable, f32),
+    Portrait,
+    Landscape,
+    ColorScheme(String)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Variable {
+    Width,
+    Height,
+    PixelRatio
+}
+
+// Takes the top level `@media` rules out of the loaded stylesheets, in
+// source order, along with `@theme` rules if themes are enabled. Their text
+// is removed from what's handed to Servo for the stylesheet itself.
+pub fn extract(loaded: &mut [Loaded], themes: bool) -> (Vec<MediaRule>, Vec<Error>) {
+    let mut media = vec![];
+    let mut errors = vec![];
+
+    for (index, loaded) in loaded.iter_mut().enumerate() {
+        for rule in mem::replace(&mut loaded.sheet.rules, vec![]) {
+            let rule = match rule {
+                Rule::At(rule) => rule,
+                rule => {
+                    loaded.sheet.rules.push(rule);
+                    continue;
+                }
+            };
+            let theme = themes && rule.name == "theme";
+            if rule.name != "media" && !theme {
+                loaded.sheet.rules.push(Rule::At(rule));
+                continue;
+            }
+            let (rule_start, rule_end) = (rule.offset, rule.end);
+            let rules = match rule.block {
+                Some(AtRuleBlock::Rules(rules)) => rules,
+                block => {
+                    loaded.sheet.rules.push(Rule::At(AtRule { block, ..rule }));
+                    continue;
+                }
+            };
+
+            loaded.edits.push(Edit {
+                start: rule_start,
+                end: rule_end,
+                text: String::new()
+            });
+
+            let condition = if theme { parse_theme(&rule.prelude) } else { parse_condition(&rule.prelude) };
+            let condition = match condition {
+                Ok(condition) => condition,
+                Err(message) => {
+                    errors.push(loaded.source.error(rule_start, message));
+                    continue;
+                }
+            };
+
+            // The block is everything between the braces, if it's closed.
+            let text = &loaded.source.text[..rule_end];
+            let start = text[rule_start..].find('{').map_or(rule_end, |offset| rule_start + offset + 1);
+            let end = if text.ends_with('}') { rule_end - 1 } else { rule_end };
+            media.push(MediaRule {
+                condition,
+                css: imports::edited(loaded, start, end),
+                sheet: Sheet { rules },
+                loaded: index,
+                offset: rule_start
+            });
+        }
+    }
+
+    (media, errors)
+}
+
+// Looks up the declarations of a selector along with those it has in the
+// `@media` rules matching the given viewport, in source order. The width and
+// height are in CSS pixels, and the color scheme is `light` or `dark`.
+pub fn method(media: &[MediaRule]) -> Tokens {
+    let lookups: Vec<Tokens> = media
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| {
+            let condition = &rule.condition;
+            let selectors = selectors(&rule.sheet);
+            if selectors.is_empty() {
+                return None;
+            }
+            Some(quote! {
+                if #condition && [#(#selectors),*].contains(&selector) {
+                    declarations.0.extend(self.1[#index].get(selector).0);
+                }
+            })
+        })
+        .collect();
+
+    quote! {
+        #[allow(dead_code, unused_mut)]
+        pub fn get_for_viewport(&mut self, selector: &str, width: f32, height: f32, pixel_ratio: f32, color_scheme: &str) -> StyleDeclarations {
+            let _ = (width, height, pixel_ratio, color_scheme);
+
+            let mut declarations = self.0.get(selector);
+            #(#lookups)*
+            declarations
+        }
+    }
+}
+
+// The selectors of the style rules in an `@media` block, as they end up in
+// its stylesheet.
+pub fn selectors(sheet: &Sheet) -> Vec<String> {
+    let mut found = vec![];
+
+    for rule in &sheet.rules {
+        if let Rule::Style(ref rule) = *rule {
+            for selector in selectors::split(&rule.selectors) {
+                let selector = selectors::serialize(&selector);
+                if !found.contains(&selector) {
+                    found.push(selector);
+                }
+            }
+        }
+    }
+
+    found
+}
+
+impl ToTokens for Condition {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        match *self {
+            Condition::Always => tokens.append("true"),
+            Condition::Never => tokens.append("false"),
+            Condition::Any(ref conditions) => join(conditions, "||", tokens),
+            Condition::All(ref conditions) => join(conditions, "&&", tokens),
+            Condition::Not(ref condition) => {
+                tokens.append("!");
+                tokens.append("(");
+                condition.to_tokens(tokens);
+                tokens.append(")");
+            }
+            Condition::AtLeast(variable, value) => compare(variable, ">=", value, tokens),
+            Condition::AtMost(variable, value) => compare(variable, "<=", value, tokens),
+            Condition::Portrait => tokens.append("(height >= width)"),
+            Condition::Landscape => tokens.append("(width > height)"),
+            Condition::ColorScheme(ref scheme) => {
+                let scheme = scheme.as_str();
+                quote!((color_scheme == #scheme)).to_tokens(tokens);
+            }
+        }
+    }
+}
+
+fn join(conditions: &[Condition], operator: &str, tokens: &mut Tokens) {
+    tokens.append("(");
+    for (index, condition) in conditions.iter().enumerate() {
+        if index > 0 {
+            tokens.append(operator);
+        }
+        condition.to_tokens(tokens);
+    }
+    tokens.append(")");
+}
+
+fn compare(variable: Variable, operator: &str, value: f32, tokens: &mut Tokens) {
+    let variable = match variable {
+        Variable::Width => "width",
+        Variable::Height => "height",
+        Variable::PixelRatio => "pixel_ratio"
+    };
+    tokens.append(&format!("({} {} {:?}f32)", variable, operator, value));
+}
+
+// Parses a media query list, e.g. `screen and (min-width: 600px), print`.
+pub fn parse_condition(prelude: &str) -> result::Result<Condition, String> {
+    let prelude = prelude.trim().to_lowercase();
+    if prelude.is_empty() {
+        return Ok(Condition::Always);
+    }
+
+    let mut conditions = vec![];
+    for query in split_queries(&prelude) {
+        conditions.push(parse_query(query.trim())?);
+    }
+
+    if conditions.len() == 1 {
+        Ok(conditions.pop().unwrap())
+    } else {
+        Ok(Condition::Any(conditions))
+    }
+}
+
+// Themes are named like color schemes, so that `@theme dark` is the same
+// as `@media (prefers-color-scheme: dark)`.
+fn parse_theme(prelude: &str) -> result::Result<Condition, String> {
+    let name = prelude.trim();
+    if name.is_empty() || !name.chars().all(is_ident_char) {
+        return Err("expected a theme name, e.g. `@theme dark`".to_string());
+    }
+    Ok(Condition::ColorScheme(name.to_string()))
+}
+
+// A query is an optional media type followed by features joined with `and`,
+// and is negated as a whole by a leading `not`.
+fn parse_query(query: &str) -> result::Result<Condition, String> {
+    let words = words(query)?;
+    let mut conditions = vec![];
+    let mut index = 0;
+
+    let negated = words.first().map_or(false, |word| word == "not");
+    if negated || words.first().map_or(false, |word| word == "only") {
+        index += 1;
+    }
+
+    let mut expect_feature = true;
+    if let Some(word) = words.get(index) {
+        if !word.starts_with('(') {
+            conditions.push(media_type(word)?);
+            index += 1;
+            expect_feature = false;
+        }
+    }
+
+    loop {
+        if !expect_feature {
+            match words.get(index) {
+                None => break,
+                Some(word) if word == "and" => index += 1,
+                Some(word) => return Err(format!("expected `and` in media query, found `{}`", word))
+            }
+        }
+        match words.get(index) {
+            Some(word) if word.starts_with('(') => conditions.push(feature(&word[1..word.len() - 1])?),
+            Some(word) => return Err(format!("expected a media feature, e.g. `(min-width: 600px)`, found `{}`", word)),
+            None => return Err("expected a media type or feature, e.g. `(min-width: 600px)`".to_string())
+        }
+        index += 1;
+        expect_feature = false;
+    }
+
+    let condition = if conditions.len() == 1 {
+        conditions.pop().unwrap()
+    } else {
+        Condition::All(conditions)
+    };
+
+    if negated {
+        Ok(Condition::Not(Box::new(condition)))
+    } else {
+        Ok(condition)
+    }
+}
+
+fn media_type(name: &str) -> result::Result<Condition, String> {
+    match name {
+        "all" | "screen" => Ok(Condition::Always),
+        "print" | "speech" => Ok(Condition::Never),
+        _ => Err(format!("unknown media type `{}`", name))
+    }
+}
+
+fn feature(text: &str) -> result::Result<Condition, String> {
+    let text = text.trim();
+    if text.contains(|c| c == '<' || c == '>' || c == '=') {
+        return Err(format!("unsupported media feature `({})`, use e.g. `min-width` instead of comparisons", text));
+    }
+
+    let (name, value) = match text.find(':') {
+        Some(index) => (text[..index].trim(), text[index + 1..].trim()),
+        None => return Err(format!("unsupported media feature `({})`", text))
+    };
+
+    match name {
+        "min-width" => Ok(Condition::AtLeast(Variable::Width, length(value)?)),
+        "max-width" => Ok(Condition::AtMost(Variable::Width, length(value)?)),
+        "width" => exactly(Variable::Width, length(value)?),
+        "min-height" => Ok(Condition::AtLeast(Variable::Height, length(value)?)),
+        "max-height" => Ok(Condition::AtMost(Variable::Height, length(value)?)),
+        "height" => exactly(Variable::Height, length(value)?),
+        "min-resolution" => Ok(Condition::AtLeast(Variable::PixelRatio, resolution(value)?)),
+        "max-resolution" => Ok(Condition::AtMost(Variable::PixelRatio, resolution(value)?)),
+        "resolution" => exactly(Variable::PixelRatio, resolution(value)?),
+        "orientation" => match value {
+            "portrait" => Ok(Condition::Portrait),
+            "landscape" => Ok(Condition::Landscape),
+            _ => Err(format!("expected `portrait` or `landscape`, found `{}`", value))
+        },
+        "prefers-color-scheme" => match value {
+            "light" | "dark" => Ok(Condition::ColorScheme(value.to_string())),
+            _ => Err(format!("expected `light` or `dark`, found `{}`", value))
+        },
+        _ => Err(format!("unsupported media feature `{}`", name))
+    }
+}
+
+fn exactly(variable: Variable, value: f32) -> result::Result<Condition, String> {
+    Ok(Condition::All(vec![Condition::AtLeast(variable, value), Condition::AtMost(variable, value)]))
+}
+
+// Lengths are in pixels, with `em` relative to the initial font size.
+fn length(value: &str) -> result::Result<f32, String> {
+    let units = [("px", 1.0), ("rem", 16.0), ("em", 16.0)];
+    if value == "0" {
+        return Ok(0.0);
+    }
+    number(value, &units).ok_or_else(|| format!("expected a length in `px` or `em`, found `{}`", value))
+}
+
+// Resolutions are in device pixels per CSS pixel.
+fn resolution(value: &str) -> result::Result<f32, String> {
+    let units = [("dppx", 1.0), ("x", 1.0), ("dpi", 1.0 / DPI), ("dpcm", 2.54 / DPI)];
+    number(value, &units).ok_or_else(|| format!("expected a resolution in `dppx`, `dpi` or `dpcm`, found `{}`", value))
+}
+
+fn number(value: &str, units: &[(&str, f32)]) -> Option<f32> {
+    let &(unit, scale) = units.iter().find(|&&(unit, _)| value.ends_with(unit))?;
+    let number: f32 = value[..value.len() - unit.len()].parse().ok()?;
+    Some(number * scale)
+}
+
+// Splits a media query list on the commas which aren't in a feature.
+fn split_queries(text: &str) -> Vec<&str> {
+    let mut queries = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                queries.push(&text[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    queries.push(&text[start..]);
+
+    queries
+}
+
+// Splits a media query into words and parenthesized features, parentheses
+// included.
+fn words(query: &str) -> result::Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut depth = 0;
+
+    for c in query.chars() {
+        match c {
+            '(' => {
+                if depth == 0 && !word.is_empty() {
+                    words.push(mem::replace(&mut word, String::new()));
+                }
+                depth += 1;
+                word.push(c);
+            }
+            ')' if depth == 0 => return Err("unexpected `)` in media query".to_string()),
+            ')' => {
+                depth -= 1;
+                word.push(c);
+                if depth == 0 {
+                    words.push(mem::replace(&mut word, String::new()));
+                }
+            }
+            _ if c.is_whitespace() && depth == 0 => {
+                if !word.is_empty() {
+                    words.push(mem::replace(&mut word, String::new()));
+                }
+            }
+            _ => word.push(c)
+        }
+    }
+
+    if depth > 0 {
+        return Err("expected `)` in media query".to_string());
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    Ok(words)
+}
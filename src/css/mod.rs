@@ -0,0 +1,278 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+mod accessors;
+mod classes;
+mod imports;
+mod inline;
+mod interpolate;
+mod matching;
+mod media;
+mod named;
+mod parse;
+mod properties;
+mod scope;
+mod selectors;
+mod states;
+mod stylesheet;
+mod themes;
+mod types;
+mod validate;
+mod variables;
+
+use std::path::PathBuf;
+use std::result;
+
+use proc_macro::{TokenStream, TokenTree};
+use quote::Tokens;
+
+use errors::{self, Error, Result};
+use files::{track_dependencies, Root};
+use literals::string_literal;
+use options::Options;
+use source::Source;
+use tokens;
+
+pub use self::classes::Classes;
+pub use self::inline::{parse_style, InlineStyle};
+use self::named::Named;
+use self::stylesheet::parse_stylesheet;
+
+// How to report declarations and rules which would otherwise be dropped,
+// set with e.g. `css!(lint = warn; ...)`. Syntax errors are always errors.
+#[derive(Clone, Copy, PartialEq)]
+enum Lint {
+    Allow,
+    Warn,
+    Deny
+}
+
+// Named stylesheets are declared where items are expected, so their errors
+// have to be items as well.
+pub fn expand(input: TokenStream) -> TokenStream {
+    let (options, input) = Options::parse(input);
+    let is_named = named::is_named(&options);
+
+    match expand_stylesheet(options, input) {
+        Ok(expanded) => expanded,
+        Err(errors) => if is_named {
+            errors::into_compile_error_items(errors)
+        } else {
+            errors::into_compile_errors(errors)
+        }
+    }
+}
+
+fn expand_stylesheet(mut options: Options, input: TokenStream) -> result::Result<TokenStream, Vec<Error>> {
+    let lint = lint_level(&mut options).map_err(|error| vec![error])?;
+    let root = Root::from_options(&mut options).map_err(|error| vec![error])?;
+    let scoped = options.flag("scoped").map_err(|error| vec![error])?;
+    let typed = options.flag("accessors").map_err(|error| vec![error])?;
+    let stateful = options.flag("states").map_err(|error| vec![error])?;
+    let responsive = options.flag("media").map_err(|error| vec![error])?;
+    let themed = options.flag("themes").map_err(|error| vec![error])?;
+    let matched = options.flag("matching").map_err(|error| vec![error])?;
+    let named = named::take(&mut options, scoped).map_err(|error| vec![error])?;
+    options.finish().map_err(|error| vec![error])?;
+
+    let source = load(input, root).map_err(|error| vec![error])?;
+    let (mut loaded, mut errors) = imports::load(source, root);
+    errors.extend(variables::substitute(&mut loaded));
+    // Accessors are named after the classes as written, before scoping.
+    let accessors = if typed {
+        let (accessors, accessor_errors) = accessors::collect(&loaded);
+        errors.extend(accessor_errors);
+        Some(accessors)
+    } else {
+        None
+    };
+    let classes = if scoped {
+        let (classes, scope_errors) = scope::scope(&mut loaded);
+        errors.extend(scope_errors);
+        Some(classes)
+    } else {
+        None
+    };
+    // States and elements are matched against the selectors as they end up
+    // in the stylesheet.
+    let state_rules = if stateful { Some(states::collect(&loaded)) } else { None };
+    let matched_rules = if matched { Some(matching::collect(&loaded)) } else { None };
+    errors.extend(interpolate::extract(&mut loaded));
+    let media = if responsive || themed {
+        let (media, media_errors) = media::extract(&mut loaded, themed);
+        errors.extend(media_errors);
+        if !responsive {
+            errors.extend(themes::check(&loaded, &media));
+        }
+        media
+    } else {
+        vec![]
+    };
+
+    if lint != Lint::Allow {
+        let sheets = loaded
+            .iter()
+            .map(|loaded| (&loaded.source, &loaded.sheet))
+            .chain(media.iter().map(|media| (&loaded[media.loaded].source, &media.sheet)));
+        for (source, sheet) in sheets {
+            for diagnostic in validate::validate(sheet) {
+                if lint == Lint::Warn {
+                    source.warning(diagnostic.offset, diagnostic.message);
+                } else {
+                    errors.push(source.error(diagnostic.offset, diagnostic.message));
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let expanded = if interpolate::has_interpolations(&loaded) {
+        interpolate::stylesheet(&loaded)
+    } else {
+        let stylesheet = parse_stylesheet(&imports::merge(&loaded));
+        quote! {
+            #stylesheet
+        }
+    };
+
+    let mut methods = vec![];
+    if let Some(ref accessors) = accessors {
+        methods.extend(accessors::methods(accessors, classes.as_ref()));
+    }
+    if let Some(ref state_rules) = state_rules {
+        methods.push(states::method(state_rules));
+    }
+    if responsive {
+        methods.push(media::method(&media));
+    }
+    if themed {
+        methods.push(themes::method(&media));
+    }
+    if let Some(ref matched_rules) = matched_rules {
+        methods.push(matching::method(matched_rules));
+    }
+    let media_sheets: Vec<Tokens> = media
+        .iter()
+        .map(|media| {
+            if interpolate::is_interpolated(&media.sheet) {
+                interpolate::sheet_stylesheet(&media.sheet)
+            } else {
+                let stylesheet = parse_stylesheet(&media.css);
+                quote! { #stylesheet }
+            }
+        })
+        .collect();
+    let dependencies: Vec<PathBuf> = loaded
+        .iter()
+        .filter_map(|loaded| loaded.source.path())
+        .map(PathBuf::from)
+        .collect();
+    let expanded = match named {
+        Some(ref named) => declare(named, expanded, &media_sheets, &methods, &dependencies),
+        None => {
+            let expanded = if typed || stateful || responsive || themed || matched {
+                wrap(expanded, &media_sheets, &methods)
+            } else {
+                expanded
+            };
+            let expanded = match classes {
+                Some(ref classes) => scope::expand(expanded, classes),
+                None => expanded
+            };
+            track_dependencies(&dependencies, expanded)
+        }
+    };
+
+    Ok(tokens::restore(expanded))
+}
+
+// Stylesheets with accessors, or state, viewport, theme or element lookups
+// are wrapped into a struct with the methods for them, along with the
+// stylesheets of their `@media` and `@theme` rules.
+fn wrap(stylesheet: Tokens, media: &[Tokens], methods: &[Tokens]) -> Tokens {
+    let name = quote! { __CssStylesheet };
+    let declaration = declaration(&Tokens::new(), &name, methods);
+
+    quote! {
+        {
+            #declaration
+
+            __CssStylesheet(#stylesheet, vec![#(#media),*])
+        }
+    }
+}
+
+// Same as `wrap`, but declaring the struct under the given name, with a
+// `new` method creating the stylesheet.
+fn declare(named: &Named, stylesheet: Tokens, media: &[Tokens], methods: &[Tokens], dependencies: &[PathBuf]) -> Tokens {
+    let name = &named.name;
+    let declaration = declaration(&named.visibility, name, methods);
+    let created = track_dependencies(dependencies, quote! { #name(#stylesheet, vec![#(#media),*]) });
+
+    quote! {
+        #declaration
+
+        impl #name {
+            #[allow(dead_code)]
+            pub fn new() -> Self {
+                #created
+            }
+        }
+    }
+}
+
+fn declaration(visibility: &Tokens, name: &Tokens, methods: &[Tokens]) -> Tokens {
+    quote! {
+        #visibility struct #name(Stylesheet, Vec<Stylesheet>);
+
+        impl #name {
+            #(#methods)*
+
+            #[allow(dead_code)]
+            pub fn stylesheet(&mut self) -> &mut Stylesheet {
+                &mut self.0
+            }
+
+            #[allow(dead_code)]
+            pub fn into_stylesheet(self) -> Stylesheet {
+                self.0
+            }
+        }
+    }
+}
+
+fn lint_level(options: &mut Options) -> Result<Lint> {
+    let entry = match options.take("lint") {
+        Some(entry) => entry,
+        None => return Ok(Lint::Deny)
+    };
+
+    match entry.term()?.as_str() {
+        "allow" => Ok(Lint::Allow),
+        "warn" => Ok(Lint::Warn),
+        "deny" => Ok(Lint::Deny),
+        _ => Err(Error::new(entry.span, "expected one of `allow`, `warn` or `deny`"))
+    }
+}
+
+// The input is either a single string literal naming a CSS file, or inline CSS.
+fn load(input: TokenStream, root: Root) -> Result<Source> {
+    let trees: Vec<TokenTree> = input.clone().into_iter().collect();
+
+    if let Some((span, path)) = string_literal(&trees) {
+        return Source::from_file(root.resolve(&path, span)?, span);
+    }
+
+    Ok(Source::from_tokens(input))
+}
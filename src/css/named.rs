@@ -0,0 +1,65 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use proc_macro::TokenStream;
+use quote::Tokens;
+
+use errors::{Error, Result};
+use options::{Entry, Options};
+use tokens::{placeholder, term_of};
+
+// The struct declared for a stylesheet with `css!(type = pub Styles; ...)`,
+// so that code can name it, e.g. to store or return it.
+pub struct Named {
+    pub visibility: Tokens,
+    pub name: Tokens
+}
+
+// Whether the stylesheet is named, in which case the macro is used where
+// items are expected, and so are its errors.
+pub fn is_named(options: &Options) -> bool {
+    options.has("type")
+}
+
+// Scoped stylesheets evaluate to a tuple along with their class names, which
+// can't be named either, so they can't be combined with a name.
+pub fn take(options: &mut Options, scoped: bool) -> Result<Option<Named>> {
+    let entry = match options.take("type") {
+        Some(entry) => entry,
+        None => return Ok(None)
+    };
+
+    if scoped {
+        return Err(Error::new(entry.span, "the `type` and `scoped` options can't be combined"));
+    }
+    parse(&entry).map(Some)
+}
+
+// The value is a name, optionally after a visibility like `pub` or
+// `pub(crate)`.
+fn parse(entry: &Entry) -> Result<Named> {
+    let error = || Error::new(entry.span, "expected a struct name, e.g. `type = pub Styles;`");
+
+    let (name, visibility) = entry.value.split_last().ok_or_else(&error)?;
+    let is_visibility = match visibility.first().and_then(term_of) {
+        Some(ref term) => term == "pub",
+        None => visibility.is_empty()
+    };
+    if term_of(name).is_none() || !is_visibility {
+        return Err(error());
+    }
+
+    let visibility: TokenStream = visibility.iter().cloned().collect();
+    Ok(Named {
+        visibility: placeholder(visibility),
+        name: placeholder(TokenStream::from(name.clone()))
+    })
+}
@@ -0,0 +1,309 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use css::types::*;
+
+// At-rules whose block contains rules rather than declarations.
+const GROUPING_RULES: [&str; 4] = ["document", "media", "supports", "theme"];
+
+pub fn parse(text: &str) -> (Sheet, Vec<Diagnostic>) {
+    let mut parser = Parser {
+        text,
+        offset: 0,
+        errors: vec![]
+    };
+
+    let rules = parser.parse_rules(false);
+    (Sheet { rules }, parser.errors)
+}
+
+// Parses the contents of a block, such as the value of a `style` attribute.
+pub fn parse_declaration_list(text: &str) -> (Vec<Declaration>, Vec<Diagnostic>) {
+    let mut parser = Parser {
+        text,
+        offset: 0,
+        errors: vec![]
+    };
+
+    let mut declarations = vec![];
+    loop {
+        declarations.extend(parser.parse_declarations());
+        let offset = parser.offset;
+        match parser.peek() {
+            Some(c) => {
+                parser.error(offset, format!("unexpected `{}`", c));
+                parser.bump();
+            }
+            None => break
+        }
+    }
+
+    (declarations, parser.errors)
+}
+
+pub fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c as u32 > 0x7f
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    offset: usize,
+    errors: Vec<Diagnostic>
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.offset..].chars().next()
+    }
+
+    fn bump(&mut self) {
+        if let Some(c) = self.peek() {
+            self.offset += c.len_utf8();
+        }
+    }
+
+    fn error<S>(&mut self, offset: usize, message: S)
+    where
+        S: Into<String>
+    {
+        self.errors.push(Diagnostic {
+            message: message.into(),
+            offset
+        });
+    }
+
+    fn skip_whitespace(&mut self) {
+        let text = self.text;
+        loop {
+            let rest = &text[self.offset..];
+            if rest.starts_with("/*") {
+                match rest[2..].find("*/") {
+                    Some(end) => self.offset += end + 4,
+                    None => {
+                        let offset = self.offset;
+                        self.error(offset, "unterminated comment");
+                        self.offset = text.len();
+                    }
+                }
+            } else if self.peek().map_or(false, char::is_whitespace) {
+                self.bump();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn skip_string(&mut self, quote: char) {
+        let offset = self.offset;
+        self.bump();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => return self.bump(),
+                Some('\\') => {
+                    self.bump();
+                    self.bump();
+                }
+                Some('\n') | None => return self.error(offset, "unterminated string"),
+                Some(_) => self.bump()
+            }
+        }
+    }
+
+    // Advances up to the first of the given characters which isn't nested
+    // inside parentheses, brackets, braces, strings or comments.
+    fn read_until(&mut self, stops: &[char]) -> (usize, usize) {
+        let text = self.text;
+        let start = self.offset;
+        let mut depth = 0;
+
+        while let Some(c) = self.peek() {
+            if depth == 0 && stops.contains(&c) {
+                break;
+            }
+            match c {
+                '"' | '\'' => {
+                    self.skip_string(c);
+                    continue;
+                }
+                '/' if text[self.offset..].starts_with("/*") => {
+                    self.skip_whitespace();
+                    continue;
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth > 0 => depth -= 1,
+                '\\' => self.bump(),
+                _ => {}
+            }
+            self.bump();
+        }
+
+        (start, self.offset)
+    }
+
+    fn slice(&self, (start, end): (usize, usize)) -> (String, usize) {
+        let raw = &self.text[start..end];
+        let leading = raw.len() - raw.trim_left().len();
+        (raw.trim().to_string(), start + leading)
+    }
+
+    fn expect_close(&mut self, offset: usize) {
+        if self.peek() == Some('}') {
+            self.bump();
+        } else {
+            self.error(offset, "unclosed block, expected `}`");
+        }
+    }
+
+    fn parse_rules(&mut self, nested: bool) -> Vec<Rule> {
+        let mut rules = vec![];
+
+        loop {
+            self.skip_whitespace();
+            let offset = self.offset;
+            match self.peek() {
+                None => break,
+                Some('}') if nested => break,
+                Some(c) if c == '}' || c == ';' => {
+                    self.error(offset, format!("unexpected `{}`", c));
+                    self.bump();
+                }
+                Some('@') => rules.push(Rule::At(self.parse_at_rule())),
+                Some(_) => if let Some(rule) = self.parse_style_rule() {
+                    rules.push(Rule::Style(rule));
+                }
+            }
+        }
+
+        rules
+    }
+
+    fn parse_style_rule(&mut self) -> Option<StyleRule> {
+        let range = self.read_until(&['{', ';', '}']);
+        let (selectors, offset) = self.slice(range);
+
+        if self.peek() != Some('{') {
+            self.error(offset, format!("expected `{{` after `{}`", selectors));
+            if self.peek() == Some(';') {
+                self.bump();
+            }
+            return None;
+        }
+        if selectors.is_empty() {
+            self.error(offset, "expected a selector before `{`");
+        }
+
+        self.bump();
+        let declarations = self.parse_declarations();
+        self.expect_close(offset);
+
+        Some(StyleRule {
+            selectors,
+            declarations,
+            interpolated: vec![],
+            offset,
+            end: self.offset
+        })
+    }
+
+    fn parse_declarations(&mut self) -> Vec<Declaration> {
+        let mut declarations = vec![];
+
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None | Some('}') => break,
+                Some(';') => self.bump(),
+                Some(_) => if let Some(declaration) = self.parse_declaration() {
+                    declarations.push(declaration);
+                }
+            }
+        }
+
+        declarations
+    }
+
+    fn parse_declaration(&mut self) -> Option<Declaration> {
+        let range = self.read_until(&[':', ';', '}']);
+        let (name, offset) = self.slice(range);
+
+        if self.peek() != Some(':') {
+            self.error(offset, format!("expected `:` after `{}`", name));
+            return None;
+        }
+        if name.is_empty() || !name.chars().all(is_ident_char) {
+            self.error(offset, format!("invalid property name `{}`", name));
+        }
+
+        self.bump();
+        let range = self.read_until(&[';', '}']);
+        let (value, value_offset) = self.slice(range);
+        let end = value_offset + value.len();
+
+        if value.is_empty() {
+            self.error(offset, format!("expected a value for property `{}`", name));
+            return None;
+        }
+
+        Some(Declaration {
+            name,
+            value,
+            offset,
+            value_offset,
+            end
+        })
+    }
+
+    fn parse_at_rule(&mut self) -> AtRule {
+        let offset = self.offset;
+        self.bump();
+
+        let start = self.offset;
+        while self.peek().map_or(false, is_ident_char) {
+            self.bump();
+        }
+        let name = self.text[start..self.offset].to_string();
+        if name.is_empty() {
+            self.error(offset, "expected an at-rule name after `@`");
+        }
+
+        let range = self.read_until(&[';', '{', '}']);
+        let (prelude, _) = self.slice(range);
+
+        let block = match self.peek() {
+            Some('{') => {
+                self.bump();
+                let block = if GROUPING_RULES.contains(&name.as_str()) {
+                    AtRuleBlock::Rules(self.parse_rules(true))
+                } else {
+                    AtRuleBlock::Declarations(self.parse_declarations())
+                };
+                self.expect_close(offset);
+                Some(block)
+            }
+            Some(';') => {
+                self.bump();
+                None
+            }
+            _ => {
+                self.error(offset, format!("expected `;` or `{{` after `@{}`", name));
+                None
+            }
+        };
+
+        AtRule {
+            name,
+            prelude,
+            block,
+            offset,
+            end: self.offset
+        }
+    }
+}
@@ -0,0 +1,103 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::cmp;
+
+// Properties which the stylesheet layer maps onto layout or theme styles.
+// Only used for wording diagnostics, whether a declaration is supported is
+// always decided by actually parsing it.
+pub const SUPPORTED_PROPERTIES: [&str; 51] = [
+    "align-content",
+    "align-items",
+    "align-self",
+    "background-color",
+    "border",
+    "border-bottom",
+    "border-bottom-width",
+    "border-left",
+    "border-left-width",
+    "border-right",
+    "border-right-width",
+    "border-top",
+    "border-top-width",
+    "border-width",
+    "bottom",
+    "color",
+    "display",
+    "flex",
+    "flex-basis",
+    "flex-direction",
+    "flex-flow",
+    "flex-grow",
+    "flex-shrink",
+    "flex-wrap",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "height",
+    "justify-content",
+    "left",
+    "margin",
+    "margin-bottom",
+    "margin-left",
+    "margin-right",
+    "margin-top",
+    "max-height",
+    "max-width",
+    "min-height",
+    "min-width",
+    "opacity",
+    "overflow",
+    "padding",
+    "padding-bottom",
+    "padding-left",
+    "padding-right",
+    "padding-top",
+    "position",
+    "right",
+    "top",
+    "width"
+];
+
+pub fn is_supported(name: &str) -> bool {
+    SUPPORTED_PROPERTIES.contains(&name)
+}
+
+// Finds the supported property closest to a misspelled one, if any is close
+// enough to be a plausible typo.
+pub fn suggest(name: &str) -> Option<&'static str> {
+    let threshold = cmp::max(1, name.len() / 3);
+
+    SUPPORTED_PROPERTIES
+        .iter()
+        .map(|property| (edit_distance(name, property), *property))
+        .filter(|&(distance, _)| distance <= threshold)
+        .min()
+        .map(|(_, property)| property)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..b.len() + 1).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + if ca == *cb { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = cmp::min(substitution, cmp::min(row[j], row[j + 1]) + 1);
+        }
+    }
+
+    row[b.len()]
+}
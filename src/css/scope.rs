@@ -0,0 +1,175 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use quote::{Ident, Tokens};
+
+use errors::Error;
+
+use css::imports::Loaded;
+use css::parse::is_ident_char;
+use css::types::*;
+
+// Words which can't be field names.
+const KEYWORDS: [&str; 52] = [
+    "Self", "abstract", "alignof", "as", "become", "box", "break", "const", "continue", "crate", "do", "else", "enum", "extern",
+    "false", "final", "fn", "for", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move", "mut", "offsetof",
+    "override", "priv", "proc", "pub", "pure", "ref", "return", "self", "sizeof", "static", "struct", "super", "trait", "true",
+    "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield"
+];
+
+// A class of a scoped stylesheet, with the name it's rewritten to and the
+// field holding that name.
+pub struct ScopedClass {
+    pub name: String,
+    pub scoped: String,
+    pub field: String
+}
+
+// Rewrites every class in the selectors of the loaded stylesheets into a
+// name unique to them, so that classes of the same name in different
+// stylesheets don't collide once merged. The suffix is derived from their
+// text, so that it's the same on every build.
+pub fn scope(loaded: &mut [Loaded]) -> (Vec<ScopedClass>, Vec<Error>) {
+    let suffix = format!("{:08x}", hash(loaded));
+    let mut classes: Vec<ScopedClass> = vec![];
+    let mut errors = vec![];
+
+    for loaded in loaded.iter_mut() {
+        let mut found = vec![];
+        scope_rules(&mut loaded.sheet.rules, &suffix, &mut found, &mut loaded.edits);
+
+        for (name, offset) in found {
+            if classes.iter().any(|class| class.name == name) {
+                continue;
+            }
+            let field = name.replace('-', "_");
+            let message = if !is_field_name(&field) {
+                Some(format!("class `{}` can't be used as a field name", name))
+            } else {
+                classes
+                    .iter()
+                    .find(|class| class.field == field)
+                    .map(|class| format!("classes `{}` and `{}` would both be the field `{}`", class.name, name, field))
+            };
+            if let Some(message) = message {
+                errors.push(loaded.source.error(offset, message));
+                continue;
+            }
+            classes.push(ScopedClass {
+                scoped: format!("{}_{}", name, suffix),
+                name,
+                field
+            });
+        }
+    }
+
+    (classes, errors)
+}
+
+// Evaluates to the stylesheet along with a struct holding the scoped name
+// of every class, so that referring to a class which doesn't exist fails
+// to compile.
+pub fn expand(stylesheet: Tokens, classes: &[ScopedClass]) -> Tokens {
+    // Borrowed, since they're repeated twice.
+    let fields: &Vec<Ident> = &classes.iter().map(|class| Ident::new(class.field.as_str())).collect();
+    let names: Vec<&str> = classes.iter().map(|class| class.scoped.as_str()).collect();
+
+    quote! {
+        {
+            #[allow(dead_code)]
+            struct __CssClasses {
+                #(#fields: &'static str),*
+            }
+            (#stylesheet, __CssClasses { #(#fields: #names),* })
+        }
+    }
+}
+
+fn scope_rules(rules: &mut [Rule], suffix: &str, found: &mut Vec<(String, usize)>, edits: &mut Vec<Edit>) {
+    for rule in rules {
+        match *rule {
+            Rule::Style(ref mut rule) => {
+                let renamed = rename(&rule.selectors, rule.offset, suffix, found);
+                if renamed != rule.selectors {
+                    edits.push(Edit {
+                        start: rule.offset,
+                        end: rule.offset + rule.selectors.len(),
+                        text: renamed.clone()
+                    });
+                    rule.selectors = renamed;
+                }
+            }
+            Rule::At(AtRule {
+                block: Some(AtRuleBlock::Rules(ref mut rules)),
+                ..
+            }) => scope_rules(rules, suffix, found, edits),
+            Rule::At(_) => {}
+        }
+    }
+}
+
+// Appends the suffix to every class in a selector list, recording each class
+// with its offset. Strings, e.g. in attribute selectors, are left alone.
+fn rename(selectors: &str, offset: usize, suffix: &str, found: &mut Vec<(String, usize)>) -> String {
+    let mut output = String::new();
+    let mut chars = selectors.char_indices().peekable();
+    let mut quote = None;
+
+    while let Some((index, c)) = chars.next() {
+        output.push(c);
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '.') => {
+                let mut name = String::new();
+                loop {
+                    match chars.peek() {
+                        Some(&(_, next)) if is_ident_char(next) => name.push(next),
+                        _ => break
+                    }
+                    chars.next();
+                }
+                if !name.is_empty() {
+                    output.push_str(&name);
+                    output.push('_');
+                    output.push_str(suffix);
+                    found.push((name, offset + index + 1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    output
+}
+
+pub fn is_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let valid = match chars.next() {
+        Some(first) if first.is_alphabetic() || first == '_' => chars.all(|c| c.is_alphanumeric() || c == '_'),
+        _ => false
+    };
+    valid && name != "_" && !KEYWORDS.contains(&name)
+}
+
+// FNV-1a, which unlike the hashers of the standard library is guaranteed to
+// give the same result with every compiler.
+fn hash(loaded: &[Loaded]) -> u32 {
+    let mut hash = 0x811c_9dc5u32;
+    for loaded in loaded {
+        for byte in loaded.source.text.bytes() {
+            hash ^= u32::from(byte);
+            hash = hash.wrapping_mul(0x0100_0193);
+        }
+    }
+    hash
+}
@@ -0,0 +1,203 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::cmp;
+
+use css::parse::is_ident_char;
+
+// Pseudo-elements which may also be written with a single colon.
+const LEGACY_PSEUDO_ELEMENTS: [&str; 4] = ["after", "before", "first-letter", "first-line"];
+
+// Splits a selector list on the commas which aren't nested inside brackets,
+// parentheses or strings.
+pub fn split(selectors: &str) -> Vec<String> {
+    let mut list = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+
+    for c in selectors.chars() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') | (None, '[') => depth += 1,
+            (None, ')') | (None, ']') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                list.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+
+    list.push(current.trim().to_string());
+    list
+}
+
+// Writes a selector the way Servo serializes it: whitespace collapsed, and
+// combinators other than the descendant one surrounded by single spaces.
+pub fn serialize(selector: &str) -> String {
+    let mut output = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut space = false;
+
+    for c in selector.trim().chars() {
+        if let Some(q) = quote {
+            if c == q {
+                quote = None;
+            }
+            output.push(c);
+            continue;
+        }
+        match c {
+            c if c.is_whitespace() && depth == 0 => {
+                space = true;
+                continue;
+            }
+            '>' | '+' | '~' if depth == 0 => {
+                output = output.trim_right().to_string();
+                output.push(' ');
+                output.push(c);
+                output.push(' ');
+                space = false;
+                continue;
+            }
+            '"' | '\'' => quote = Some(c),
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            _ => {}
+        }
+        if space && !output.ends_with(' ') {
+            output.push(' ');
+        }
+        space = false;
+        output.push(c);
+    }
+
+    output
+}
+
+// The class a selector consists of, if it's a single class.
+pub fn single_class(selector: &str) -> Option<String> {
+    if !selector.starts_with('.') || selector.len() == 1 {
+        return None;
+    }
+    let name = &selector[1..];
+    if name.chars().all(is_ident_char) {
+        Some(name.to_string())
+    } else {
+        None
+    }
+}
+
+// Ids, then classes, attributes and pseudo-classes, then type selectors and
+// pseudo-elements, packed into ten bits each.
+pub fn specificity(selector: &str) -> u32 {
+    let (ids, classes, elements) = count(&selector.chars().collect::<Vec<_>>());
+    (ids << 20) | (classes << 10) | elements
+}
+
+fn count(chars: &[char]) -> (u32, u32, u32) {
+    let (mut ids, mut classes, mut elements) = (0, 0, 0);
+    let mut index = 0;
+
+    while index < chars.len() {
+        match chars[index] {
+            '#' => {
+                ids += 1;
+                index = skip_ident(chars, index + 1);
+            }
+            '.' => {
+                classes += 1;
+                index = skip_ident(chars, index + 1);
+            }
+            '[' => {
+                classes += 1;
+                index = skip_nested(chars, index);
+            }
+            ':' if chars.get(index + 1) == Some(&':') => {
+                elements += 1;
+                index = skip_ident(chars, index + 2);
+            }
+            ':' => {
+                let end = skip_ident(chars, index + 1);
+                let name: String = chars[index + 1..end].iter().collect::<String>().to_lowercase();
+                index = end;
+
+                if chars.get(index) == Some(&'(') {
+                    let close = skip_nested(chars, index);
+                    // Negations count as whatever they contain.
+                    if name == "not" {
+                        let inner_end = if chars[close - 1] == ')' { close - 1 } else { close };
+                        let (a, b, c) = count(&chars[index + 1..inner_end]);
+                        ids += a;
+                        classes += b;
+                        elements += c;
+                        index = close;
+                        continue;
+                    }
+                    index = close;
+                }
+
+                if LEGACY_PSEUDO_ELEMENTS.contains(&name.as_str()) {
+                    elements += 1;
+                } else {
+                    classes += 1;
+                }
+            }
+            c if is_ident_char(c) => {
+                elements += 1;
+                index = skip_ident(chars, index);
+            }
+            _ => index += 1
+        }
+    }
+
+    (ids, classes, elements)
+}
+
+fn skip_ident(chars: &[char], mut index: usize) -> usize {
+    while index < chars.len() && (is_ident_char(chars[index]) || chars[index] == '\\') {
+        index += if chars[index] == '\\' { 2 } else { 1 };
+    }
+    cmp::min(index, chars.len())
+}
+
+// Skips over a bracketed or parenthesized part, returning the index right
+// after the matching closing character.
+fn skip_nested(chars: &[char], mut index: usize) -> usize {
+    let mut depth = 0;
+    let mut quote = None;
+
+    while index < chars.len() {
+        let c = chars[index];
+        index += 1;
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') | (None, '[') => depth += 1,
+            (None, ')') | (None, ']') => {
+                depth -= 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    index
+}
@@ -0,0 +1,127 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use quote::Tokens;
+
+use css::imports::Loaded;
+use css::selectors;
+use css::types::*;
+
+// Pseudo-classes which depend on the state of an element, and so can only
+// be matched at runtime.
+const STATES: [&str; 4] = ["active", "disabled", "focus", "hover"];
+
+// A rule which only applies to elements in some states, e.g. `.foo:hover`,
+// with the selector it applies to otherwise, e.g. `.foo`.
+pub struct StateRule {
+    pub base: String,
+    pub states: Vec<String>,
+    pub selector: String
+}
+
+pub fn collect(loaded: &[Loaded]) -> Vec<StateRule> {
+    let mut rules = vec![];
+
+    for loaded in loaded {
+        for rule in &loaded.sheet.rules {
+            let rule = match *rule {
+                Rule::Style(ref rule) => rule,
+                Rule::At(_) => continue
+            };
+            for selector in selectors::split(&rule.selectors) {
+                let selector = selectors::serialize(&selector);
+                if let Some((base, states)) = split_states(&selector) {
+                    rules.push(StateRule { base, states, selector });
+                }
+            }
+        }
+    }
+
+    rules
+}
+
+// Looks up the declarations of a selector along with those of the rules
+// applying to it in the given states, in source order. Rules whose states
+// aren't all given are left out.
+pub fn method(rules: &[StateRule]) -> Tokens {
+    let count = rules.len();
+    let entries: Vec<Tokens> = rules
+        .iter()
+        .map(|rule| {
+            let base = rule.base.as_str();
+            let states = &rule.states;
+            let selector = rule.selector.as_str();
+            quote! { (#base, &[#(#states),*], #selector) }
+        })
+        .collect();
+
+    quote! {
+        #[allow(dead_code)]
+        pub fn get_for_state(&mut self, selector: &str, states: &[&str]) -> StyleDeclarations {
+            const RULES: [(&str, &[&str], &str); #count] = [#(#entries),*];
+
+            let mut declarations = self.0.get(selector);
+            for &(base, required, rule) in RULES.iter() {
+                if base == selector && required.iter().all(|state| states.contains(state)) {
+                    declarations.0.extend(self.0.get(rule).0);
+                }
+            }
+            declarations
+        }
+    }
+}
+
+// Removes the state pseudo-classes from a serialized selector, returning
+// what remains along with the states, if there are any. Compounds left empty
+// match any element.
+fn split_states(selector: &str) -> Option<(String, Vec<String>)> {
+    let chars: Vec<char> = selector.chars().collect();
+    let mut base = String::new();
+    let mut states = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let c = chars[index];
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, '(') | (None, '[') => depth += 1,
+            (None, ')') | (None, ']') => depth -= 1,
+            (None, ':') if depth == 0 && chars.get(index + 1) != Some(&':') => {
+                let end = chars[index + 1..]
+                    .iter()
+                    .position(|&c| !c.is_alphanumeric() && c != '-')
+                    .map_or(chars.len(), |length| index + 1 + length);
+                let name: String = chars[index + 1..end].iter().collect::<String>().to_lowercase();
+                if STATES.contains(&name.as_str()) && chars.get(end) != Some(&'(') {
+                    if base.is_empty() || base.ends_with(' ') {
+                        base.push('*');
+                    }
+                    states.push(name);
+                    index = end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        base.push(c);
+        index += 1;
+    }
+
+    if states.is_empty() {
+        None
+    } else {
+        Some((base, states))
+    }
+}
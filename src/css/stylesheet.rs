@@ -0,0 +1,46 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use rsx_stylesheet::servo_css_parser::parse as parse_css;
+use rsx_stylesheet::servo_css_parser::types::*;
+use rsx_stylesheet::types::{StyleDeclarations, Stylesheet};
+
+pub const DECLARATIONS_SELECTOR: &str = ".declarations";
+
+pub fn parse_stylesheet(css: &str) -> Stylesheet {
+    let origin = Origin::UserAgent;
+    let qm = QuirksMode::NoQuirks;
+    let media = MediaList::empty();
+    let url = Url::parse("about::inline").unwrap();
+
+    let parsed = parse_css(css, url, origin, qm, media);
+    Stylesheet::from(parsed).ignore_unused()
+}
+
+// Whether Servo accepts a rule's selectors. An invalid selector drops the
+// whole rule, along with `DECLARATIONS_SELECTOR` listed next to it.
+pub fn is_valid_selector(selectors: &str) -> bool {
+    let css = format!("{}, {} {{ width: 1px }}", selectors, DECLARATIONS_SELECTOR);
+    !parse_stylesheet(&css).get(DECLARATIONS_SELECTOR).0.is_empty()
+}
+
+// Parses a list of declarations, as found inside a rule's block, keeping
+// only what the stylesheet layer is able to represent.
+pub fn parse_declarations(css: &str) -> StyleDeclarations {
+    let mut stylesheet = parse_declaration_block(css);
+    stylesheet.get(DECLARATIONS_SELECTOR)
+}
+
+// Parses a list of declarations into a stylesheet with a single rule, whose
+// selector is `DECLARATIONS_SELECTOR`.
+pub fn parse_declaration_block(css: &str) -> Stylesheet {
+    parse_stylesheet(&format!("{} {{ {} }}", DECLARATIONS_SELECTOR, css))
+}
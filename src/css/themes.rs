@@ -0,0 +1,73 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use quote::Tokens;
+
+use errors::Error;
+
+use css::imports::Loaded;
+use css::media::{self, Condition, MediaRule};
+
+// Without the `media` option, only rules depending on nothing but the color
+// scheme are allowed.
+pub fn check(loaded: &[Loaded], media: &[MediaRule]) -> Vec<Error> {
+    media
+        .iter()
+        .filter(|rule| theme(&rule.condition).is_none())
+        .map(|rule| {
+            loaded[rule.loaded].source.error(
+                rule.offset,
+                "only `@media (prefers-color-scheme: ...)` rules can be themed, other media queries need `css!(media; ...)`"
+            )
+        })
+        .collect()
+}
+
+// Looks up the declarations of a selector along with those it has in the
+// rules of the given theme, e.g. `dark`, in source order. Every theme's
+// stylesheet is parsed at compile-time, so switching themes is only a
+// different lookup.
+pub fn method(media: &[MediaRule]) -> Tokens {
+    let lookups: Vec<Tokens> = media
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| {
+            let theme = theme(&rule.condition)?;
+            let selectors = media::selectors(&rule.sheet);
+            if selectors.is_empty() {
+                return None;
+            }
+            Some(quote! {
+                if theme == #theme && [#(#selectors),*].contains(&selector) {
+                    declarations.0.extend(self.1[#index].get(selector).0);
+                }
+            })
+        })
+        .collect();
+
+    quote! {
+        #[allow(dead_code, unused_mut)]
+        pub fn get_for_theme(&mut self, selector: &str, theme: &str) -> StyleDeclarations {
+            let _ = theme;
+
+            let mut declarations = self.0.get(selector);
+            #(#lookups)*
+            declarations
+        }
+    }
+}
+
+fn theme(condition: &Condition) -> Option<&str> {
+    match *condition {
+        Condition::ColorScheme(ref name) => Some(name.as_str()),
+        _ => None
+    }
+}
@@ -0,0 +1,90 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+// A coarse outline of a stylesheet, only as detailed as the macro needs for
+// diagnostics and preprocessing. Parsing the actual values is left to Servo.
+// All offsets are byte offsets into the parsed text, and ends are exclusive.
+
+use proc_macro::TokenStream;
+
+#[derive(Debug, Default)]
+pub struct Sheet {
+    pub rules: Vec<Rule>
+}
+
+#[derive(Debug)]
+pub enum Rule {
+    Style(StyleRule),
+    At(AtRule)
+}
+
+#[derive(Debug)]
+pub struct StyleRule {
+    pub selectors: String,
+    pub declarations: Vec<Declaration>,
+    pub interpolated: Vec<Interpolated>,
+    pub offset: usize,
+    pub end: usize
+}
+
+#[derive(Debug)]
+pub struct Declaration {
+    pub name: String,
+    pub value: String,
+    pub offset: usize,
+    pub value_offset: usize,
+    pub end: usize
+}
+
+// A declaration whose value is a Rust expression, evaluated at run time.
+#[derive(Debug)]
+pub struct Interpolated {
+    pub name: String,
+    pub expression: TokenStream,
+    pub unit: Unit,
+    pub offset: usize
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    None,
+    Px,
+    Percent
+}
+
+#[derive(Debug)]
+pub struct AtRule {
+    pub name: String,
+    pub prelude: String,
+    pub block: Option<AtRuleBlock>,
+    pub offset: usize,
+    pub end: usize
+}
+
+#[derive(Debug)]
+pub enum AtRuleBlock {
+    Rules(Vec<Rule>),
+    Declarations(Vec<Declaration>)
+}
+
+// Replaces part of the parsed text before it's handed to Servo.
+#[derive(Debug)]
+pub struct Edit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub offset: usize
+}
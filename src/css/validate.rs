@@ -0,0 +1,81 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use css::properties;
+use css::stylesheet::{is_valid_selector, parse_declarations};
+use css::types::*;
+use css::variables;
+
+// Finds everything `Stylesheet::from` would otherwise silently drop: rules it
+// doesn't handle or whose selectors it rejects, and declarations it can't
+// represent. The latter are found by running each declaration through the
+// parser on its own.
+pub fn validate(sheet: &Sheet) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    validate_rules(&sheet.rules, &mut diagnostics);
+    diagnostics
+}
+
+fn validate_rules(rules: &[Rule], diagnostics: &mut Vec<Diagnostic>) {
+    for rule in rules {
+        match *rule {
+            Rule::Style(ref rule) if !is_valid_selector(&rule.selectors) => diagnostics.push(Diagnostic {
+                message: format!("invalid selector `{}`", rule.selectors),
+                offset: rule.offset
+            }),
+            Rule::Style(ref rule) => validate_declarations(&rule.declarations, diagnostics),
+            // Resolved before validation, see `css::imports`.
+            Rule::At(ref rule) if rule.name == "import" => {}
+            // Taken out before validation when enabled, see `css::media` and `css::themes`.
+            Rule::At(ref rule) if rule.name == "media" => diagnostics.push(Diagnostic {
+                message: "`@media` rules are only supported with `css!(media; ...)`".to_string(),
+                offset: rule.offset
+            }),
+            Rule::At(ref rule) if rule.name == "theme" => diagnostics.push(Diagnostic {
+                message: "`@theme` rules are only supported with `css!(themes; ...)`".to_string(),
+                offset: rule.offset
+            }),
+            Rule::At(ref rule) => diagnostics.push(Diagnostic {
+                message: format!("`@{}` rules are not supported", rule.name),
+                offset: rule.offset
+            })
+        }
+    }
+}
+
+pub fn validate_declarations(declarations: &[Declaration], diagnostics: &mut Vec<Diagnostic>) {
+    for declaration in declarations {
+        // Checked while substituting custom properties.
+        if variables::is_custom(&declaration.name) || variables::has_references(&declaration.value) {
+            continue;
+        }
+        let source = format!("{}: {}", declaration.name, declaration.value);
+        if parse_declarations(&source).0.is_empty() {
+            diagnostics.push(Diagnostic {
+                message: describe(declaration),
+                offset: declaration.offset
+            });
+        }
+    }
+}
+
+fn describe(declaration: &Declaration) -> String {
+    let name = declaration.name.as_str();
+
+    if properties::is_supported(name) {
+        return format!("invalid value `{}` for property `{}`", declaration.value, name);
+    }
+
+    match properties::suggest(name) {
+        Some(suggestion) => format!("unknown property `{}`, did you mean `{}`?", name, suggestion),
+        None => format!("unknown or unsupported property `{}`", name)
+    }
+}
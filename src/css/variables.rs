@@ -0,0 +1,281 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use errors::Error;
+
+use css::imports::Loaded;
+use css::parse::is_ident_char;
+use css::types::*;
+
+// Custom properties are substituted at compile-time, so they're global:
+// they can only be defined in `:root` rules, in any loaded stylesheet, and
+// later definitions override earlier ones. The definitions themselves are
+// removed from the text handed to Servo.
+pub fn substitute(loaded: &mut [Loaded]) -> Vec<Error> {
+    let mut resolver = Resolver {
+        definitions: HashMap::new(),
+        resolved: HashMap::new(),
+        stack: vec![],
+        errors: vec![]
+    };
+
+    for (sheet, loaded) in loaded.iter_mut().enumerate() {
+        for rule in &loaded.sheet.rules {
+            let rule = match *rule {
+                Rule::Style(ref rule) => rule,
+                Rule::At(_) => continue
+            };
+
+            let custom: Vec<&Declaration> = rule.declarations.iter().filter(|declaration| is_custom(&declaration.name)).collect();
+            if custom.is_empty() {
+                continue;
+            }
+            if rule.selectors != ":root" {
+                let location = Location {
+                    sheet,
+                    offset: rule.offset
+                };
+                resolver.error(location, "custom properties can only be defined in `:root`");
+                continue;
+            }
+
+            for declaration in &custom {
+                let definition = Definition {
+                    value: declaration.value.clone(),
+                    location: Location {
+                        sheet,
+                        offset: declaration.offset
+                    }
+                };
+                resolver.definitions.insert(declaration.name.clone(), definition);
+            }
+
+            if is_definitions(rule) {
+                loaded.edits.push(removal(rule.offset, rule.end));
+            } else {
+                loaded.edits.extend(custom.iter().map(|declaration| removal(declaration.offset, declaration.end)));
+            }
+        }
+    }
+
+    // The definitions are gone from the text, so drop them from the outline.
+    for loaded in loaded.iter_mut() {
+        loaded.sheet.rules.retain(|rule| match *rule {
+            Rule::Style(ref rule) => !is_definitions(rule),
+            Rule::At(_) => true
+        });
+        for rule in &mut loaded.sheet.rules {
+            if let Rule::Style(ref mut rule) = *rule {
+                rule.declarations.retain(|declaration| !is_custom(&declaration.name));
+            }
+        }
+    }
+
+    for (sheet, loaded) in loaded.iter_mut().enumerate() {
+        substitute_rules(&mut loaded.sheet.rules, sheet, &mut resolver, &mut loaded.edits);
+    }
+
+    resolver
+        .errors
+        .into_iter()
+        .map(|(location, message)| loaded[location.sheet].source.error(location.offset, message))
+        .collect()
+}
+
+// References are substituted in rules nested in at-rules too, e.g. `@media`,
+// but definitions only come from the top level.
+fn substitute_rules(rules: &mut [Rule], sheet: usize, resolver: &mut Resolver, edits: &mut Vec<Edit>) {
+    for rule in rules {
+        let rule = match *rule {
+            Rule::Style(ref mut rule) => rule,
+            Rule::At(AtRule {
+                block: Some(AtRuleBlock::Rules(ref mut rules)),
+                ..
+            }) => {
+                substitute_rules(rules, sheet, resolver, edits);
+                continue;
+            }
+            Rule::At(_) => continue
+        };
+
+        for declaration in &mut rule.declarations {
+            if is_custom(&declaration.name) || !has_references(&declaration.value) {
+                continue;
+            }
+            let location = Location {
+                sheet,
+                offset: declaration.offset
+            };
+            if let Some(value) = resolver.substitute(&declaration.value, location) {
+                edits.push(Edit {
+                    start: declaration.value_offset,
+                    end: declaration.end,
+                    text: value.clone()
+                });
+                declaration.value = value;
+            }
+        }
+    }
+}
+
+pub fn is_custom(name: &str) -> bool {
+    name.starts_with("--")
+}
+
+// Whether a rule is a `:root` rule only defining custom properties, which is
+// removed altogether.
+fn is_definitions(rule: &StyleRule) -> bool {
+    rule.selectors == ":root" && !rule.declarations.is_empty() && rule.declarations.iter().all(|declaration| is_custom(&declaration.name))
+}
+
+pub fn has_references(value: &str) -> bool {
+    find_var(value).is_some()
+}
+
+fn removal(start: usize, end: usize) -> Edit {
+    Edit {
+        start,
+        end,
+        text: String::new()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Location {
+    sheet: usize,
+    offset: usize
+}
+
+#[derive(Clone)]
+struct Definition {
+    value: String,
+    location: Location
+}
+
+#[derive(Clone)]
+enum Lookup {
+    Value(String),
+    Undefined,
+    // Already reported as an error.
+    Invalid
+}
+
+struct Resolver {
+    definitions: HashMap<String, Definition>,
+    resolved: HashMap<String, Lookup>,
+    // The custom properties currently being resolved, outermost first.
+    stack: Vec<String>,
+    errors: Vec<(Location, String)>
+}
+
+impl Resolver {
+    fn error<S>(&mut self, location: Location, message: S)
+    where
+        S: Into<String>
+    {
+        self.errors.push((location, message.into()));
+    }
+
+    fn lookup(&mut self, name: &str) -> Lookup {
+        if let Some(resolved) = self.resolved.get(name) {
+            return resolved.clone();
+        }
+        let definition = match self.definitions.get(name) {
+            Some(definition) => definition.clone(),
+            None => return Lookup::Undefined
+        };
+
+        if let Some(index) = self.stack.iter().position(|parent| parent == name) {
+            let cycle = self.stack[index..].join(" -> ");
+            let message = format!("custom property `{}` depends on itself: {} -> {}", name, cycle, name);
+            self.error(definition.location, message);
+            return Lookup::Invalid;
+        }
+
+        self.stack.push(name.to_string());
+        let lookup = match self.substitute(&definition.value, definition.location) {
+            Some(value) => Lookup::Value(value),
+            None => Lookup::Invalid
+        };
+        self.stack.pop();
+
+        self.resolved.insert(name.to_string(), lookup.clone());
+        lookup
+    }
+
+    // Replaces every `var()` in the given value, reporting problems at the
+    // given location. Fallbacks are only looked at when they're needed.
+    fn substitute(&mut self, value: &str, location: Location) -> Option<String> {
+        let mut output = String::new();
+        let mut rest = value;
+
+        while let Some(start) = find_var(rest) {
+            output.push_str(&rest[..start]);
+
+            let arguments_start = start + "var(".len();
+            let length = match closing_paren(&rest[arguments_start..]) {
+                Some(length) => length,
+                None => {
+                    self.error(location, "unclosed `var(`");
+                    return None;
+                }
+            };
+            let arguments = &rest[arguments_start..arguments_start + length];
+            rest = &rest[arguments_start + length + 1..];
+
+            // Names can't contain commas, so the first one ends the name.
+            let (name, fallback) = match arguments.find(',') {
+                Some(comma) => (arguments[..comma].trim(), Some(arguments[comma + 1..].trim())),
+                None => (arguments.trim(), None)
+            };
+            if !is_custom(name) || !name.chars().all(is_ident_char) {
+                self.error(location, format!("expected a custom property name in `var({})`", arguments.trim()));
+                return None;
+            }
+
+            match self.lookup(name) {
+                Lookup::Value(resolved) => output.push_str(&resolved),
+                Lookup::Invalid => return None,
+                Lookup::Undefined => match fallback {
+                    Some(fallback) => output.push_str(&self.substitute(fallback, location)?),
+                    None => {
+                        self.error(location, format!("undefined custom property `{}`", name));
+                        return None;
+                    }
+                }
+            }
+        }
+
+        output.push_str(rest);
+        Some(output)
+    }
+}
+
+fn find_var(text: &str) -> Option<usize> {
+    text.match_indices("var(")
+        .map(|(index, _)| index)
+        .find(|&index| !text[..index].chars().next_back().map_or(false, is_ident_char))
+}
+
+fn closing_paren(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(index),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
@@ -0,0 +1,78 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::result;
+
+use proc_macro::{Delimiter, Literal, Spacing, Span, Term, TokenNode, TokenStream, TokenTree};
+
+pub type Result<T> = result::Result<T, Error>;
+
+#[derive(Debug)]
+pub struct Error {
+    pub span: Span,
+    pub message: String
+}
+
+impl Error {
+    pub fn new<S>(span: Span, message: S) -> Self
+    where
+        S: Into<String>
+    {
+        Error {
+            span,
+            message: message.into()
+        }
+    }
+
+    pub fn into_compile_error(self) -> TokenStream {
+        self.compile_error().into_iter().collect()
+    }
+
+    // Same as `into_compile_error`, but usable where an item is expected.
+    pub fn into_compile_error_item(self) -> TokenStream {
+        let span = self.span;
+        let mut trees = self.compile_error();
+        trees.push(TokenTree {
+            span,
+            kind: TokenNode::Op(';', Spacing::Alone)
+        });
+        trees.into_iter().collect()
+    }
+
+    // Builds `compile_error!("...")`, with every token carrying the span
+    // of the offending input so that rustc underlines the right place.
+    fn compile_error(self) -> Vec<TokenTree> {
+        let span = self.span;
+        let tree = |kind| TokenTree { span, kind };
+
+        let message = TokenStream::from(tree(TokenNode::Literal(Literal::string(&self.message))));
+
+        vec![
+            tree(TokenNode::Term(Term::intern("compile_error"))),
+            tree(TokenNode::Op('!', Spacing::Alone)),
+            tree(TokenNode::Group(Delimiter::Parenthesis, message)),
+        ]
+    }
+}
+
+// Expands to a block containing one `compile_error!` statement per error,
+// so that all of them are reported at once.
+pub fn into_compile_errors(errors: Vec<Error>) -> TokenStream {
+    TokenStream::from(TokenTree {
+        span: Span::call_site(),
+        kind: TokenNode::Group(Delimiter::Brace, into_compile_error_items(errors))
+    })
+}
+
+// Same as `into_compile_errors`, but usable where items are expected.
+pub fn into_compile_error_items(errors: Vec<Error>) -> TokenStream {
+    errors.into_iter().map(Error::into_compile_error_item).collect()
+}
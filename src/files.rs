@@ -0,0 +1,95 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use proc_macro::Span;
+use quote::Tokens;
+
+use errors::{Error, Result};
+use options::Options;
+
+// What relative paths given to a macro are resolved against, set with
+// e.g. `css!(relative_to = file; "style.css")`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Root {
+    // The manifest directory of the crate invoking the macro.
+    Manifest,
+    // The directory of the source file invoking the macro.
+    File
+}
+
+impl Root {
+    pub fn from_options(options: &mut Options) -> Result<Root> {
+        let entry = match options.take("relative_to") {
+            Some(entry) => entry,
+            None => return Ok(Root::Manifest)
+        };
+
+        match entry.term()?.as_str() {
+            "manifest" => Ok(Root::Manifest),
+            "file" => Ok(Root::File),
+            _ => Err(Error::new(entry.span, "expected one of `manifest` or `file`"))
+        }
+    }
+
+    pub fn resolve(self, path: &str, span: Span) -> Result<PathBuf> {
+        let base = match self {
+            Root::Manifest => manifest_dir(),
+            Root::File => source_dir(span)?
+        };
+        Ok(base.join(path))
+    }
+}
+
+// Cargo always sets this when building, fall back to the working directory
+// when the compiler is invoked some other way.
+fn manifest_dir() -> PathBuf {
+    env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| env::current_dir().unwrap())
+}
+
+// Paths inside the invoking crate are shown relative to its manifest, which
+// keeps diagnostics short.
+pub fn display(path: &Path) -> String {
+    let manifest_dir = manifest_dir();
+    path.strip_prefix(&manifest_dir).unwrap_or(path).to_string_lossy().into_owned()
+}
+
+fn source_dir(span: Span) -> Result<PathBuf> {
+    let file = span.source_file();
+    if !file.is_real() {
+        return Err(Error::new(span, "can't resolve a path relative to a file which doesn't exist"));
+    }
+
+    let path = env::current_dir().unwrap().join(PathBuf::from(file.as_str()));
+    let dir = path.parent().map(PathBuf::from);
+    Ok(dir.unwrap_or(path))
+}
+
+// Wraps an expansion into a block that includes the given files, so that
+// changing any of them causes the invoking crate to be rebuilt.
+pub fn track_dependencies(files: &[PathBuf], expanded: Tokens) -> Tokens {
+    if files.is_empty() {
+        return expanded;
+    }
+
+    let files: Vec<String> = files.iter().map(|file| file.to_string_lossy().into_owned()).collect();
+
+    quote! {
+        {
+            #(let _ = include_str!(#files);)*
+            #expanded
+        }
+    }
+}
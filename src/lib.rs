@@ -10,84 +10,49 @@ specific language governing permissions and limitations under the License.
 */
 
 #![feature(proc_macro)]
+#![recursion_limit = "128"]
 
 extern crate proc_macro;
 #[macro_use]
 extern crate quote;
-extern crate regex;
-extern crate rsx_dom;
 extern crate rsx_stylesheet;
-
-use std::env;
-use std::fs::File;
-use std::io::Read;
-
-use regex::Regex;
-use rsx_dom::rsx_parser::parse as parse_rsx;
-use rsx_stylesheet::servo_css_parser::parse as parse_css;
-use rsx_stylesheet::servo_css_parser::types::*;
-use rsx_stylesheet::types::Stylesheet;
+extern crate syn;
+
+mod css;
+mod errors;
+mod files;
+mod literals;
+mod options;
+mod props;
+mod rsx;
+mod source;
+mod tokens;
 
 #[proc_macro]
 pub fn rsx(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let source = input.to_string();
-    let (ast, _) = parse_rsx(&source).unwrap();
-
-    let expanded = quote! {
-        #ast
-    };
+    match rsx::expand(input) {
+        Ok(expanded) => expanded,
+        Err(error) => error.into_compile_error()
+    }
+}
 
-    expanded.parse().unwrap()
+#[proc_macro]
+pub fn rsx_file(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match rsx::expand_file(input) {
+        Ok(expanded) => expanded,
+        Err(error) => error.into_compile_error()
+    }
 }
 
 #[proc_macro]
 pub fn css(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let source = input.to_string();
-    let len = source.len();
-
-    let origin = Origin::UserAgent;
-    let qm = QuirksMode::NoQuirks;
-    let media = MediaList::empty();
-    let url = Url::parse("about::inline").unwrap();
-
-    let css = if source.chars().nth(0).unwrap() == '"' {
-        let file_path = env::current_dir().unwrap().join(&source[1..len - 1]);
-
-        let mut result = String::new();
-        File::open(file_path.clone())
-            .expect(&format!(
-                "Couldn't open file {}",
-                file_path.to_string_lossy()
-            ))
-            .read_to_string(&mut result)
-            .expect(&format!(
-                "Couldn't read file {}",
-                file_path.to_string_lossy()
-            ));
-
-        result
-    } else {
-        // When converting TokenStreams to Strings, a whitespace is inserted between
-        // each and every token. This unfortunately means that whitespace is also
-        // inserted in selectors, turning ".foo" into ". foo" which isn't valid CSS.
-        // Same goes to rule names, such as "margin-left" becoming "margin - left".
-        // Crudely find and fix those occurrences.
-
-        let re_selectors = Regex::new(r"(?P<type>[.#])\s(?P<name>[a-zA-Z0-9]+)").unwrap();
-        let result = re_selectors.replace_all(&source, "$type$name");
-
-        let re_rules = Regex::new(r"(?P<start>[a-zA-Z0-9]+)\s-\s(?P<end>[a-zA-Z0-9]+)\s").unwrap();
-        let result = re_rules.replace_all(&result, "$start-$end");
-
-        result.into_owned()
-    };
-
-    let parsed = parse_css(&css, url, origin, qm, media);
-    let stylesheet = Stylesheet::from(parsed).ignore_unused();
-
-    let expanded = quote! {
-        #stylesheet
-    };
+    css::expand(input)
+}
 
-    expanded.parse().unwrap()
+#[proc_macro_derive(Props, attributes(prop))]
+pub fn derive_props(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match props::derive(input) {
+        Ok(expanded) => expanded,
+        Err(error) => error.into_compile_error_item()
+    }
 }
@@ -0,0 +1,88 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::char;
+
+use proc_macro::{Span, TokenNode, TokenTree};
+
+// The string denoted by the given tokens, if they're a single string literal.
+pub fn string_literal(trees: &[TokenTree]) -> Option<(Span, String)> {
+    if trees.len() != 1 {
+        return None;
+    }
+    match trees[0].kind {
+        TokenNode::Literal(ref literal) => parse_str(&literal.to_string()).map(|string| (trees[0].span, string)),
+        _ => None
+    }
+}
+
+// Turns the source representation of a string literal token (as given by
+// `Literal::to_string`) back into the string it denotes. Returns `None` for
+// anything that isn't a (raw) string literal.
+pub fn parse_str(literal: &str) -> Option<String> {
+    if literal.starts_with('r') {
+        return parse_raw_str(&literal[1..]);
+    }
+    if literal.len() < 2 || !literal.starts_with('"') || !literal.ends_with('"') {
+        return None;
+    }
+
+    let mut result = String::new();
+    let mut chars = literal[1..literal.len() - 1].chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            '0' => result.push('\0'),
+            '\\' => result.push('\\'),
+            '\'' => result.push('\''),
+            '"' => result.push('"'),
+            'x' => {
+                let digits: String = chars.by_ref().take(2).collect();
+                let code = u8::from_str_radix(&digits, 16).ok()?;
+                result.push(code as char);
+            }
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let digits: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let code = u32::from_str_radix(&digits.replace("_", ""), 16).ok()?;
+                result.push(char::from_u32(code)?);
+            }
+            '\n' => while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                chars.next();
+            },
+            _ => return None
+        }
+    }
+
+    Some(result)
+}
+
+fn parse_raw_str(literal: &str) -> Option<String> {
+    let hashes = literal.chars().take_while(|c| *c == '#').count();
+    let body = &literal[hashes..];
+    if body.len() < 2 + hashes || !body.starts_with('"') {
+        return None;
+    }
+    let body = &body[1..body.len() - hashes];
+    if !body.ends_with('"') {
+        return None;
+    }
+    Some(body[..body.len() - 1].to_string())
+}
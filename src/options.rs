@@ -0,0 +1,102 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use proc_macro::{Span, TokenStream, TokenTree};
+
+use errors::{Error, Result};
+use tokens::{op_of, term_of};
+
+// Macros accept a header of `name;` or `name = value;` entries before their
+// actual input, e.g. `css!(lint = warn; .foo { ... })`.
+pub struct Options {
+    entries: Vec<Entry>
+}
+
+pub struct Entry {
+    pub name: String,
+    pub span: Span,
+    pub value: Vec<TokenTree>
+}
+
+impl Options {
+    pub fn parse(input: TokenStream) -> (Options, TokenStream) {
+        let trees: Vec<TokenTree> = input.into_iter().collect();
+        let mut entries = vec![];
+        let mut index = 0;
+
+        while let Some(name) = trees.get(index).and_then(term_of) {
+            let (value, end) = match trees.get(index + 1).and_then(op_of) {
+                Some(';') => (vec![], index + 1),
+                Some('=') => match trees[index + 2..].iter().position(|tree| op_of(tree) == Some(';')) {
+                    Some(position) => (trees[index + 2..index + 2 + position].to_vec(), index + 2 + position),
+                    None => break
+                },
+                _ => break
+            };
+
+            entries.push(Entry {
+                name,
+                span: trees[index].span,
+                value
+            });
+
+            index = end + 1;
+        }
+
+        let rest = trees[index..].iter().cloned().collect();
+        (Options { entries }, rest)
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.entries.iter().any(|entry| entry.name == name)
+    }
+
+    pub fn take(&mut self, name: &str) -> Option<Entry> {
+        let position = self.entries.iter().position(|entry| entry.name == name)?;
+        Some(self.entries.remove(position))
+    }
+
+    // Options which change what a macro evaluates to are opt-in flags, e.g.
+    // `css!(scoped; ...)`, without a value.
+    pub fn flag(&mut self, name: &str) -> Result<bool> {
+        match self.take(name) {
+            Some(entry) => entry.flag().map(|_| true),
+            None => Ok(false)
+        }
+    }
+
+    pub fn finish(self) -> Result<()> {
+        match self.entries.into_iter().next() {
+            Some(entry) => Err(Error::new(entry.span, format!("unknown option `{}`", entry.name))),
+            None => Ok(())
+        }
+    }
+}
+
+impl Entry {
+    pub fn flag(&self) -> Result<()> {
+        if self.value.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::new(self.span, format!("option `{}` doesn't take a value", self.name)))
+        }
+    }
+
+    pub fn term(&self) -> Result<String> {
+        match self.value.first().and_then(term_of) {
+            Some(ref term) if self.value.len() == 1 => Ok(term.clone()),
+            _ => Err(Error::new(
+                self.span,
+                format!("expected an identifier as the value of option `{}`", self.name)
+            ))
+        }
+    }
+}
@@ -0,0 +1,306 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use proc_macro::{Delimiter, Spacing, Span, TokenNode, TokenStream, TokenTree};
+use quote::Tokens;
+use syn::{self, Body, DeriveInput, Field, Ident, Lit, MetaItem, NestedMetaItem, PathParameters, Ty, VariantData};
+
+use errors::{Error, Result};
+use tokens::{self, respan, term_of};
+
+// A builder for a props struct, used by `rsx!` to create components. Each
+// prop is set by a method taking anything convertible into its type, and
+// props without a default are tracked in the builder's type parameters.
+// Until they're set, each is a type named after it, e.g. `GreetingMissingName`,
+// so that calling `build` too early is an error naming the missing prop.
+pub fn derive(input: TokenStream) -> Result<TokenStream> {
+    let ast = syn::parse_derive_input(&input.to_string()).map_err(|message| Error::new(Span::call_site(), message))?;
+    let props = props(&ast, &input)?;
+
+    let name = &ast.ident;
+    let vis = &ast.vis;
+    let builder = Ident::new(format!("{}Builder", name));
+    let prop_trait = Ident::new(format!("{}Prop", builder));
+
+    let required: Vec<&Prop> = props.iter().filter(|prop| prop.kind == Kind::Required).collect();
+    let others: Vec<&Prop> = props.iter().filter(|prop| prop.kind != Kind::Required).collect();
+
+    // Borrowed, since they're repeated several times.
+    let params: &Vec<Ident> = &(0..required.len()).map(|index| Ident::new(format!("__Prop{}", index))).collect();
+    let missing: &Vec<Ident> = &required
+        .iter()
+        .map(|prop| Ident::new(format!("{}Missing{}", name, camel_case(prop.name.as_ref()))))
+        .collect();
+    let required_names: &Vec<&Ident> = &required.iter().map(|prop| &prop.name).collect();
+    let required_types: Vec<&Ty> = required.iter().map(|prop| &prop.ty).collect();
+    let other_names: &Vec<&Ident> = &others.iter().map(|prop| &prop.name).collect();
+    let other_types: Vec<&Ty> = others.iter().map(|prop| prop.inner()).collect();
+    let other_setters: Vec<Tokens> = others
+        .iter()
+        .map(|prop| {
+            let field = &prop.name;
+            let ty = prop.inner();
+            let setter = quote! {
+                #vis fn #field<__Value: Into<#ty>>(mut self, value: __Value) -> Self {
+                    self.#field = Some(value.into());
+                    self
+                }
+            };
+            respan(setter, prop.span)
+        })
+        .collect();
+
+    let required_setters: Vec<Tokens> = required
+        .iter()
+        .enumerate()
+        .map(|(index, prop)| {
+            let field = &prop.name;
+            let ty = &prop.ty;
+            let marker = &missing[index];
+            let free: Vec<&Ident> = params.iter().enumerate().filter(|&(i, _)| i != index).map(|(_, param)| param).collect();
+            let before = replace(params, index, quote! { #marker });
+            let after = replace(params, index, quote! { #ty });
+            let rest: Vec<Tokens> = props
+                .iter()
+                .map(|prop| &prop.name)
+                .filter(|name| *name != field)
+                .map(|name| quote! { #name: self.#name })
+                .collect();
+            let setter = quote! {
+                #[allow(dead_code)]
+                impl<#(#free),*> #builder<#(#before),*> {
+                    #vis fn #field<__Value: Into<#ty>>(self, value: __Value) -> #builder<#(#after),*> {
+                        #builder {
+                            #field: value.into(),
+                            #(#rest),*
+                        }
+                    }
+                }
+            };
+            respan(setter, prop.span)
+        })
+        .collect();
+
+    let finish: Vec<Tokens> = props
+        .iter()
+        .map(|prop| {
+            let field = &prop.name;
+            match prop.kind {
+                Kind::Required => quote! { #field: #prop_trait::into_prop(self.#field) },
+                Kind::Optional => quote! { #field: self.#field },
+                Kind::Default(ref default) => quote! { #field: self.#field.unwrap_or_else(|| #default) }
+            }
+        })
+        .collect();
+
+    let bounds: Vec<Tokens> = params
+        .iter()
+        .zip(&required_types)
+        .map(|(param, ty)| quote! { #param: #prop_trait<#ty> })
+        .collect();
+    let markers: Vec<Tokens> = missing
+        .iter()
+        .map(|marker| {
+            quote! {
+                #[allow(dead_code)]
+                #vis struct #marker;
+            }
+        })
+        .collect();
+
+    // Props which are never set would otherwise make for warnings.
+    let expanded = quote! {
+        #[allow(dead_code)]
+        #vis struct #builder<#(#params),*> {
+            #(#required_names: #params,)*
+            #(#other_names: Option<#other_types>,)*
+        }
+
+        #(#markers)*
+
+        #[doc(hidden)]
+        #vis trait #prop_trait<T> {
+            fn into_prop(self) -> T;
+        }
+
+        impl<T> #prop_trait<T> for T {
+            fn into_prop(self) -> T {
+                self
+            }
+        }
+
+        #[allow(dead_code)]
+        impl #name {
+            #vis fn builder() -> #builder<#(#missing),*> {
+                #builder {
+                    #(#required_names: #missing,)*
+                    #(#other_names: None,)*
+                }
+            }
+        }
+
+        #(#required_setters)*
+
+        #[allow(dead_code)]
+        impl<#(#params),*> #builder<#(#params),*> {
+            #(#other_setters)*
+
+            #vis fn build(self) -> #name
+            where
+                #(#bounds),*
+            {
+                #name {
+                    #(#finish),*
+                }
+            }
+        }
+    };
+
+    Ok(tokens::restore(expanded))
+}
+
+struct Prop {
+    name: Ident,
+    ty: Ty,
+    kind: Kind,
+    // Where the field is named in the input.
+    span: Span
+}
+
+#[derive(PartialEq)]
+enum Kind {
+    Required,
+    // An `Option`, which is `None` unless set.
+    Optional,
+    // Set with `#[prop(default)]` or `#[prop(default = "expression")]`.
+    Default(Tokens)
+}
+
+impl Prop {
+    // The type taken by the prop's setter.
+    fn inner(&self) -> &Ty {
+        match self.kind {
+            Kind::Optional => option_type(&self.ty).unwrap(),
+            _ => &self.ty
+        }
+    }
+}
+
+fn props(ast: &DeriveInput, input: &TokenStream) -> Result<Vec<Prop>> {
+    let error = |message| Err(Error::new(name_span(input), message));
+
+    if !ast.generics.lifetimes.is_empty() || !ast.generics.ty_params.is_empty() {
+        return error("`#[derive(Props)]` doesn't support generic structs");
+    }
+    match ast.body {
+        Body::Struct(VariantData::Struct(ref fields)) => fields.iter().map(|field| prop(field, input)).collect(),
+        _ => error("`#[derive(Props)]` only supports structs with named fields")
+    }
+}
+
+fn prop(field: &Field, input: &TokenStream) -> Result<Prop> {
+    let name = field.ident.clone().unwrap();
+    let span = field_span(input, name.as_ref());
+    let mut kind = if option_type(&field.ty).is_some() { Kind::Optional } else { Kind::Required };
+
+    for attribute in field.attrs.iter().filter(|attribute| attribute.name() == "prop") {
+        let error = || Error::new(span, format!("expected `#[prop(default)]` or `#[prop(default = \"...\")]` on `{}`", name));
+        let items = match attribute.value {
+            MetaItem::List(_, ref items) => items,
+            _ => return Err(error())
+        };
+        for item in items {
+            kind = match *item {
+                NestedMetaItem::MetaItem(MetaItem::Word(ref word)) if word == "default" => Kind::Default(quote! { Default::default() }),
+                NestedMetaItem::MetaItem(MetaItem::NameValue(ref word, Lit::Str(ref expression, _))) if word == "default" => {
+                    let expression = syn::parse_expr(expression).map_err(|message| Error::new(span, message))?;
+                    Kind::Default(quote! { #expression })
+                }
+                _ => return Err(error())
+            };
+        }
+    }
+
+    Ok(Prop {
+        name,
+        ty: field.ty.clone(),
+        kind,
+        span
+    })
+}
+
+// syn doesn't keep spans, so they're found again in the input: the name of
+// the struct comes right after `struct`.
+fn name_span(input: &TokenStream) -> Span {
+    let trees: Vec<TokenTree> = input.clone().into_iter().collect();
+    trees
+        .windows(2)
+        .find(|pair| term_of(&pair[0]).map_or(false, |term| term == "struct"))
+        .map_or_else(Span::call_site, |pair| pair[1].span)
+}
+
+// A field is named in the braces of the struct, followed by a single `:`.
+fn field_span(input: &TokenStream, name: &str) -> Span {
+    let fields = input
+        .clone()
+        .into_iter()
+        .filter_map(|tree| match tree.kind {
+            TokenNode::Group(Delimiter::Brace, stream) => Some(stream),
+            _ => None
+        })
+        .last();
+    let trees: Vec<TokenTree> = fields.map_or_else(Vec::new, |stream| stream.into_iter().collect());
+    trees
+        .windows(2)
+        .find(|pair| {
+            let colon = match pair[1].kind {
+                TokenNode::Op(':', Spacing::Alone) => true,
+                _ => false
+            };
+            colon && term_of(&pair[0]).map_or(false, |term| term == name)
+        })
+        .map_or_else(|| name_span(input), |pair| pair[0].span)
+}
+
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new()
+            }
+        })
+        .collect()
+}
+
+// The `T` of an `Option<T>`.
+fn option_type(ty: &Ty) -> Option<&Ty> {
+    let segment = match *ty {
+        Ty::Path(None, ref path) => path.segments.last()?,
+        _ => return None
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+    match segment.parameters {
+        PathParameters::AngleBracketed(ref data) if data.types.len() == 1 => data.types.first(),
+        _ => None
+    }
+}
+
+fn replace(params: &[Ident], index: usize, replacement: Tokens) -> Vec<Tokens> {
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| if i == index { replacement.clone() } else { quote! { #param } })
+        .collect()
+}
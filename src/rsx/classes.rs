@@ -0,0 +1,107 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::path::PathBuf;
+
+use css::{Classes, InlineStyle};
+use errors::{Error, Result};
+use files::Root;
+use literals::string_literal;
+use options::Options;
+use rsx::types::*;
+
+// The stylesheet which `class` attributes are resolved against, given with
+// e.g. `rsx!(stylesheet = "styles.css"; ...)`. It's loaded at compile-time,
+// so that classes are checked and merged in the stylesheet's own order.
+pub struct Binding(Classes);
+
+impl Binding {
+    pub fn from_options(options: &mut Options, root: Root) -> Result<Option<Binding>> {
+        let entry = match options.take("stylesheet") {
+            Some(entry) => entry,
+            None => return Ok(None)
+        };
+
+        match string_literal(&entry.value) {
+            Some((span, path)) => Ok(Some(Binding(Classes::load(&path, span, root)?))),
+            None => Err(Error::new(entry.span, "expected a path to a stylesheet, e.g. `stylesheet = \"styles.css\";`"))
+        }
+    }
+
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.0.files().to_vec()
+    }
+}
+
+// Replaces `class` attributes with the styles of their classes, merged with
+// those of the `style` attribute, which come last.
+pub fn resolve(element: &mut Element, binding: &Binding) -> Result<()> {
+    let class = match element.attributes.iter().position(|attribute| attribute.name.text == "class") {
+        Some(index) => index,
+        None => return Ok(())
+    };
+    let names = match element.attributes[class].value {
+        AttributeValue::Str(ref names) => names.clone(),
+        _ => return Ok(())
+    };
+    let span = element.attributes[class].name.span;
+
+    let style = element.attributes.iter().position(|attribute| attribute.name.text == "style");
+    let style = style.map(|index| element.attributes.remove(index).value);
+    let class = element.attributes.iter().position(|attribute| attribute.name.text == "class").unwrap();
+
+    let names: Vec<&str> = names.split_whitespace().collect();
+    let classes = &binding.0;
+    let mut parts = match classes.declarations(&names) {
+        Ok(css) => vec![StylePart::Static(InlineStyle::new(css))],
+        Err(name) => {
+            let message = format!("class `{}` isn't defined in `{}`", name, classes.name());
+            return Err(Error::new(span, message));
+        }
+    };
+
+    match style {
+        Some(AttributeValue::Style(style)) => parts.push(StylePart::Static(style)),
+        Some(AttributeValue::Code(code)) => parts.push(StylePart::Code(code)),
+        Some(_) => return Err(Error::new(span, "expected the `style` attribute to be a string literal or `{...}`")),
+        None => {}
+    }
+
+    // Static parts next to each other are merged at compile-time.
+    let mut merged: Vec<StylePart> = vec![];
+    for part in parts {
+        match (merged.pop(), part) {
+            (Some(StylePart::Static(first)), StylePart::Static(second)) => {
+                let css = format!("{} {}", first.css(), second.css());
+                merged.push(StylePart::Static(InlineStyle::new(css)));
+            }
+            (previous, part) => {
+                merged.extend(previous);
+                merged.push(part);
+            }
+        }
+    }
+
+    let value = if merged.len() == 1 {
+        match merged.pop().unwrap() {
+            StylePart::Static(style) => AttributeValue::Style(style),
+            part => AttributeValue::Styles(vec![part])
+        }
+    } else {
+        AttributeValue::Styles(merged)
+    };
+
+    let attribute = &mut element.attributes[class];
+    attribute.name.text = "style".to_string();
+    attribute.value = value;
+
+    Ok(())
+}
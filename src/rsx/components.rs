@@ -0,0 +1,50 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use errors::{Error, Result};
+use rsx::types::*;
+
+// Marks elements whose name is a type, e.g. `Dropdown` or `menu.Dropdown`,
+// and checks that their attributes can be props.
+pub fn mark(element: &mut Element) -> Result<()> {
+    let segments: Vec<&str> = element.name.text.split('.').collect();
+    let capitalized = segments.last().map_or(false, |name| name.starts_with(char::is_uppercase));
+    if !capitalized || !segments.iter().all(|segment| is_identifier(segment)) {
+        return Ok(());
+    }
+
+    if element.key.is_some() {
+        return Err(Error::new(element.name.span, "components can't have a `key` yet"));
+    }
+    for attribute in &element.attributes {
+        let name = &attribute.name;
+        if let AttributeValue::Spread(_) = attribute.value {
+            return Err(Error::new(name.span, "attributes can't be spread into components"));
+        }
+        if !is_identifier(&name.text) {
+            return Err(Error::new(name.span, format!("`{}` isn't a valid prop name", name.text)));
+        }
+        if name.text == "children" && !element.children.is_empty() {
+            return Err(Error::new(name.span, "`children` are given both as a prop and as child elements"));
+        }
+    }
+
+    element.component = true;
+    Ok(())
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) if first.is_alphabetic() || first == '_' => chars.all(|c| c.is_alphanumeric() || c == '_'),
+        _ => false
+    }
+}
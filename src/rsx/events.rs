@@ -0,0 +1,69 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::mem;
+
+use proc_macro::TokenNode;
+
+use errors::{Error, Result};
+use rsx::types::*;
+
+// Moves attributes of elements which are event handlers, e.g. `onClick`,
+// into the element's listeners, whose closures are converted into the
+// node's listener type. Components take handlers as ordinary props.
+pub fn mark(element: &mut Element) -> Result<()> {
+    if element.component {
+        return Ok(());
+    }
+
+    for attribute in mem::replace(&mut element.attributes, vec![]) {
+        if !is_event_handler(&attribute.name.text) {
+            element.attributes.push(attribute);
+            continue;
+        }
+        let message = format!("expected a closure, e.g. `{}={{|event| ...}}`", attribute.name.text);
+        let code = match attribute.value {
+            AttributeValue::Code(code) => code,
+            _ => return Err(Error::new(attribute.name.span, message))
+        };
+        if is_literal(&code) {
+            return Err(Error::new(attribute.name.span, message));
+        }
+        element.listeners.push(Listener {
+            name: attribute.name.text,
+            code
+        });
+    }
+
+    Ok(())
+}
+
+// Handlers are named after their event, e.g. `onClick` or `onMouseDown`.
+fn is_event_handler(name: &str) -> bool {
+    name.starts_with("on") && name[2..].starts_with(char::is_uppercase)
+}
+
+// Literals can't be handlers, including the `true` of shorthand attributes,
+// e.g. `<button onClick>`.
+fn is_literal(code: &Code) -> bool {
+    if code.parts.len() != 1 {
+        return false;
+    }
+    let trees: Vec<TokenNode> = match code.parts[0] {
+        CodePart::Tokens(ref stream) => stream.clone().into_iter().map(|tree| tree.kind).collect(),
+        _ => return false
+    };
+    match trees.first() {
+        Some(&TokenNode::Literal(_)) => trees.len() == 1,
+        Some(&TokenNode::Term(term)) => trees.len() == 1 && (term.as_str() == "true" || term.as_str() == "false"),
+        _ => false
+    }
+}
@@ -0,0 +1,93 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use proc_macro::TokenNode;
+
+use errors::{Error, Result};
+use rsx::types::*;
+
+// Methods whose closure usually creates one element per item.
+const MAPPING_METHODS: [&str; 3] = ["filter_map", "flat_map", "map"];
+
+// Moves the `key` attribute out of the attributes, and checks that sibling
+// elements don't share the same literal key.
+pub fn extract(element: &mut Element) -> Result<()> {
+    let position = element.attributes.iter().position(|attribute| attribute.name.text == "key");
+    if let Some(index) = position {
+        let attribute = element.attributes.remove(index);
+        element.key = match attribute.value {
+            AttributeValue::Str(key) => Some(Key::Str(key)),
+            AttributeValue::Code(code) => Some(Key::Code(code)),
+            _ => return Err(Error::new(attribute.name.span, "expected a string literal or `{...}` as a key"))
+        };
+    }
+    if let Some(attribute) = element.attributes.iter().find(|attribute| attribute.name.text == "key") {
+        return Err(Error::new(attribute.name.span, "an element can only have one `key`"));
+    }
+
+    let mut keys: Vec<&str> = vec![];
+    for child in &element.children {
+        if let Child::Element(ref child) = *child {
+            if let Some(Key::Str(ref key)) = child.key {
+                if keys.contains(&key.as_str()) {
+                    return Err(Error::new(child.name.span, format!("duplicate key `{}` among siblings", key)));
+                }
+                keys.push(key);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Warns about elements created by `map` and similar closures in children
+// code without a key, since they can't be told apart when diffing.
+pub fn check(element: &mut Element) -> Result<()> {
+    for child in &element.children {
+        if let Child::Code(ref code) = *child {
+            check_code(code);
+        }
+    }
+    Ok(())
+}
+
+fn check_code(code: &Code) {
+    let mut mapping = false;
+
+    for part in &code.parts {
+        match *part {
+            CodePart::Tokens(ref stream) => {
+                mapping = match stream.clone().into_iter().last().map(|tree| tree.kind) {
+                    Some(TokenNode::Term(term)) => MAPPING_METHODS.contains(&term.as_str()),
+                    _ => false
+                };
+                continue;
+            }
+            CodePart::Group(_, ref inner) if mapping => warn_unkeyed(inner),
+            CodePart::Group(_, ref inner) => check_code(inner),
+            CodePart::Element(_) => {}
+        }
+        mapping = false;
+    }
+}
+
+fn warn_unkeyed(code: &Code) {
+    for part in &code.parts {
+        match *part {
+            CodePart::Element(ref element) if element.key.is_none() && !element.is_fragment() => {
+                let message = "elements created in a loop should have a `key` attribute";
+                element.name.span.warning(message).emit();
+            }
+            CodePart::Group(_, ref inner) => warn_unkeyed(inner),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,114 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+mod classes;
+mod components;
+mod events;
+mod keys;
+mod parse;
+mod tokenize;
+mod types;
+mod visit;
+
+use std::path::PathBuf;
+
+use proc_macro::{Span, TokenStream, TokenTree};
+
+use errors::{Error, Result};
+use files::{self, track_dependencies, Root};
+use literals::string_literal;
+use options::Options;
+use source::Source;
+use tokens;
+
+use self::classes::Binding;
+use self::types::{Child, Element};
+use self::visit::visit_elements;
+
+pub fn expand(input: TokenStream) -> Result<TokenStream> {
+    let (mut options, input) = Options::parse(input);
+    let root = Root::from_options(&mut options)?;
+    let binding = Binding::from_options(&mut options, root)?;
+    let components = options.flag("components")?;
+    options.finish()?;
+
+    let element = parse::parse(input)?;
+    transform(element, binding, components, vec![])
+}
+
+// Expands `rsx_file!`, which takes the same options as `rsx!` followed by
+// the path of a template.
+pub fn expand_file(input: TokenStream) -> Result<TokenStream> {
+    let (mut options, input) = Options::parse(input);
+    let root = Root::from_options(&mut options)?;
+    let binding = Binding::from_options(&mut options, root)?;
+    let components = options.flag("components")?;
+    options.finish()?;
+
+    let trees: Vec<TokenTree> = input.into_iter().collect();
+    let (span, path) = match string_literal(&trees) {
+        Some(literal) => literal,
+        None => {
+            let span = trees.first().map_or_else(Span::call_site, |tree| tree.span);
+            return Err(Error::new(span, "expected the path of a template, e.g. `rsx_file!(\"template.rsx\")`"));
+        }
+    };
+    let path = root.resolve(&path, span)?;
+    let element = parse::parse_template(Source::from_file(path.clone(), span)?)?;
+
+    // Errors found past parsing can't be located within the template, but
+    // at least name it.
+    let display = files::display(&path);
+    transform(element, binding, components, vec![path])
+        .map_err(|error| Error::new(span, format!("{}: {}", display, error.message)))
+}
+
+fn transform(mut element: Element, binding: Option<Binding>, components: bool, mut dependencies: Vec<PathBuf>) -> Result<TokenStream> {
+    visit_elements(&mut element, &mut keys::extract)?;
+    visit_elements(&mut element, &mut keys::check)?;
+    if let Some(ref binding) = binding {
+        visit_elements(&mut element, &mut |element| classes::resolve(element, binding))?;
+    }
+    if components {
+        visit_elements(&mut element, &mut components::mark)?;
+    }
+    visit_elements(&mut element, &mut events::mark)?;
+
+    // Helpers for splicing code into children are only needed, and only
+    // defined, when some element has code among its children.
+    let mut splices = false;
+    visit_elements(&mut element, &mut |element| {
+        splices |= element.children.iter().any(|child| match *child {
+            Child::Code(_) => true,
+            _ => false
+        });
+        Ok(())
+    })?;
+
+    let expanded = if splices {
+        let helpers = tokenize::splice_helpers();
+        quote! {
+            {
+                #helpers
+                #element
+            }
+        }
+    } else {
+        quote! {
+            #element
+        }
+    };
+
+    dependencies.extend(binding.map_or_else(Vec::new, |binding| binding.files()));
+    let expanded = track_dependencies(&dependencies, expanded);
+
+    Ok(tokens::restore(expanded))
+}
@@ -0,0 +1,573 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::rc::Rc;
+
+use proc_macro::{Delimiter, Span, TokenNode, TokenStream, TokenTree};
+
+use css::parse_style;
+use errors::{Error, Result};
+use literals::parse_str;
+use rsx::types::*;
+use source::{token_offsets, Source, Writer};
+use tokens::{op_of, term_of};
+
+// Keywords after which a `<` starts an expression rather than a comparison.
+const EXPRESSION_KEYWORDS: [&str; 5] = ["box", "break", "in", "return", "yield"];
+
+pub fn parse(input: TokenStream) -> Result<Element> {
+    let parser = Parser::new(input, Span::call_site());
+    parse_root(parser)
+}
+
+// Templates read from a file are tokenized from its text, which gives all
+// of their tokens the span of the macro invocation. Errors are located with
+// the offsets of the tokens in the file instead.
+pub fn parse_template(source: Source) -> Result<Element> {
+    let input: TokenStream = match source.text.parse() {
+        Ok(input) => input,
+        Err(_) => return Err(source.error(0, "template has unbalanced delimiters or unterminated literals"))
+    };
+    let template = Template {
+        offsets: token_offsets(&source.text, input.clone()),
+        source
+    };
+
+    let mut parser = Parser::new(input, Span::call_site());
+    parser.template = Some((Rc::new(template), 0));
+    parse_root(parser)
+}
+
+fn parse_root(mut parser: Parser) -> Result<Element> {
+    let element = parser.parse_element()?;
+
+    if !parser.is_done() {
+        return parser.unexpected("end of input");
+    }
+
+    Ok(element)
+}
+
+struct Template {
+    source: Source,
+    // The start and end offset of every token, delimiters included, in order.
+    offsets: Vec<(usize, usize)>
+}
+
+pub struct Parser {
+    trees: Vec<TokenTree>,
+    index: usize,
+    span: Span,
+    // For templates, the template and the index among all of its tokens of
+    // the first token given to this parser.
+    template: Option<(Rc<Template>, usize)>
+}
+
+impl Parser {
+    // The given span is where errors about a premature end of input point to,
+    // which is the enclosing group or the whole macro invocation.
+    pub fn new(input: TokenStream, span: Span) -> Self {
+        Parser {
+            trees: input.into_iter().collect(),
+            index: 0,
+            span,
+            template: None
+        }
+    }
+
+    // Parses the code inside the group at the given index.
+    fn parse_group(&self, index: usize, stream: TokenStream, span: Span) -> Result<Code> {
+        let mut parser = Parser::new(stream, span);
+        parser.template = self.template.as_ref().map(|&(ref template, base)| {
+            (template.clone(), token_index(&self.trees, base, index) + 1)
+        });
+        parser.parse_code()
+    }
+
+    // An error about the tree at the given index, or about the end of input
+    // if there's none.
+    fn error_at<S>(&self, index: usize, span: Span, message: S) -> Error
+    where
+        S: Into<String>
+    {
+        match self.template {
+            Some((ref template, base)) => {
+                let token = token_index(&self.trees, base, index);
+                let offset = template.offsets.get(token).map_or(template.source.text.len(), |&(start, _)| start);
+                template.source.error(offset, message)
+            }
+            None => Error::new(span, message)
+        }
+    }
+
+    // Text of templates is taken from the file, since their tokens have no
+    // positions to recreate whitespace from.
+    fn template_text(&self, start: usize) -> Option<String> {
+        let (ref template, base) = *self.template.as_ref()?;
+        let from = match start.checked_sub(1) {
+            Some(_) => template.offsets[token_index(&self.trees, base, start) - 1].1,
+            None => template.offsets[base].0
+        };
+        let to = template
+            .offsets
+            .get(token_index(&self.trees, base, self.index))
+            .map_or(template.source.text.len(), |&(start, _)| start);
+        template.source.text.get(from..to).map(str::to_string)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.index >= self.trees.len()
+    }
+
+    fn peek(&self) -> Option<&TokenTree> {
+        self.trees.get(self.index)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&TokenTree> {
+        self.trees.get(self.index + n)
+    }
+
+    fn peek_op(&self) -> Option<char> {
+        self.peek().and_then(op_of)
+    }
+
+    fn next_span(&self) -> Span {
+        self.peek().map_or(self.span, |tree| tree.span)
+    }
+
+    pub fn unexpected<T>(&self, expected: &str) -> Result<T> {
+        Err(self.error_at(
+            self.index,
+            self.next_span(),
+            format!("expected {}, found {}", expected, describe(self.peek()))
+        ))
+    }
+
+    fn expect_op(&mut self, op: char) -> Result<()> {
+        if self.peek_op() != Some(op) {
+            return self.unexpected(&format!("`{}`", op));
+        }
+        self.index += 1;
+        Ok(())
+    }
+
+    pub fn parse_element(&mut self) -> Result<Element> {
+        let span = self.next_span();
+        self.expect_op('<')?;
+
+        if self.peek_op() == Some('>') {
+            self.index += 1;
+            let name = Name { text: String::new(), span };
+            let children = self.parse_children(&name)?;
+            return Ok(Element {
+                name,
+                attributes: vec![],
+                children,
+                key: None,
+                listeners: vec![],
+                component: false
+            });
+        }
+
+        let name = self.parse_name("an element name")?;
+        let mut attributes = vec![];
+
+        loop {
+            match self.peek_op() {
+                Some('/') => {
+                    self.index += 1;
+                    self.expect_op('>')?;
+                    return Ok(Element {
+                        name,
+                        attributes,
+                        children: vec![],
+                        key: None,
+                        listeners: vec![],
+                        component: false
+                    });
+                }
+                Some('>') => {
+                    self.index += 1;
+                    break;
+                }
+                _ if self.at_spread() => attributes.push(self.parse_spread()?),
+                _ => attributes.push(self.parse_attribute()?)
+            }
+        }
+
+        let children = self.parse_children(&name)?;
+
+        Ok(Element {
+            name,
+            attributes,
+            children,
+            key: None,
+            listeners: vec![],
+            component: false
+        })
+    }
+
+    fn parse_name(&mut self, expected: &str) -> Result<Name> {
+        let span = self.next_span();
+        let mut text = match self.peek().and_then(term_of) {
+            Some(text) => text,
+            None => return self.unexpected(expected)
+        };
+        self.index += 1;
+
+        // Names such as `data-id` or `xlink:href` are split into several tokens.
+        loop {
+            let separator = match self.peek_op() {
+                Some(c) if c == '-' || c == ':' || c == '.' => c,
+                _ => break
+            };
+            let segment = match self.peek_nth(1).and_then(term_of) {
+                Some(segment) => segment,
+                None => break
+            };
+            text.push(separator);
+            text.push_str(&segment);
+            self.index += 2;
+        }
+
+        Ok(Name { text, span })
+    }
+
+    fn parse_attribute(&mut self) -> Result<Attribute> {
+        let name = self.parse_name("an attribute, `>` or `/>`")?;
+
+        // Attributes without a value, e.g. `<input disabled />`, are `true`.
+        if self.peek_op() != Some('=') {
+            let value = Code {
+                parts: vec![CodePart::Tokens("true".parse().unwrap())]
+            };
+            return Ok(Attribute {
+                name,
+                value: AttributeValue::Code(value)
+            });
+        }
+        self.index += 1;
+        let (start, span) = (self.index, self.next_span());
+
+        // Inline styles are parsed right away, like stylesheets in `css!`.
+        let value = match self.parse_attribute_value()? {
+            AttributeValue::Str(ref css) if name.text == "style" => match parse_style(css, span) {
+                Ok(style) => AttributeValue::Style(style),
+                Err(error) => return Err(self.error_at(start, error.span, error.message))
+            },
+            AttributeValue::Code(code) => {
+                if self.peek_op() == Some('?') {
+                    self.index += 1;
+                    AttributeValue::Optional(code)
+                } else {
+                    AttributeValue::Code(code)
+                }
+            }
+            value => value
+        };
+
+        Ok(Attribute { name, value })
+    }
+
+    fn at_spread(&self) -> bool {
+        match self.peek().map(|tree| &tree.kind) {
+            Some(&TokenNode::Group(Delimiter::Brace, _)) => true,
+            _ => false
+        }
+    }
+
+    fn parse_spread(&mut self) -> Result<Attribute> {
+        let (start, tree) = (self.index, self.peek().unwrap().clone());
+        self.index += 1;
+
+        let trees: Vec<TokenTree> = match tree.kind {
+            TokenNode::Group(_, ref stream) => stream.clone().into_iter().collect(),
+            _ => unreachable!()
+        };
+        let dots = trees.iter().take_while(|tree| op_of(tree) == Some('.')).count();
+        if dots != 2 || trees.len() == 2 {
+            return Err(self.error_at(start, tree.span, "expected `{..attributes}`"));
+        }
+
+        let stream = trees.into_iter().skip(2).collect();
+        Ok(Attribute {
+            name: Name {
+                text: "..".to_string(),
+                span: tree.span
+            },
+            value: AttributeValue::Spread(self.parse_group(start, stream, tree.span)?)
+        })
+    }
+
+    fn parse_attribute_value(&mut self) -> Result<AttributeValue> {
+        let expected = "a string literal, `{...}` or an element";
+        let tree = match self.peek() {
+            Some(tree) => tree.clone(),
+            None => return self.unexpected(expected)
+        };
+
+        match tree.kind {
+            TokenNode::Literal(ref literal) => match parse_str(&literal.to_string()) {
+                Some(value) => {
+                    self.index += 1;
+                    Ok(AttributeValue::Str(value))
+                }
+                None => self.unexpected(expected)
+            },
+            TokenNode::Group(Delimiter::Brace, ref stream) => {
+                self.index += 1;
+                Ok(AttributeValue::Code(self.parse_group(self.index - 1, stream.clone(), tree.span)?))
+            }
+            TokenNode::Op('<', _) => Ok(AttributeValue::Element(self.parse_element()?)),
+            _ => self.unexpected(expected)
+        }
+    }
+
+    fn parse_children(&mut self, parent: &Name) -> Result<Vec<Child>> {
+        let mut children = vec![];
+        let mut after_text = false;
+
+        loop {
+            let tree = match self.peek() {
+                Some(tree) => tree.clone(),
+                None => {
+                    return Err(self.error_at(
+                        self.index,
+                        parent.span,
+                        format!("expected `</{}>`, found end of input", parent.text)
+                    ))
+                }
+            };
+
+            // Whitespace alone between tags or code, e.g. in `<b>a</b> <i>b</i>`,
+            // is kept unless it spans several lines. Text already includes the
+            // whitespace after it.
+            if !after_text && !is_text(&tree) {
+                let text = self.parse_text();
+                if !text.is_empty() {
+                    children.push(Child::Text(text));
+                }
+            }
+            after_text = is_text(&tree);
+
+            match tree.kind {
+                TokenNode::Op('<', _) => {
+                    if self.peek_nth(1).and_then(op_of) != Some('/') {
+                        children.push(Child::Element(self.parse_element()?));
+                        continue;
+                    }
+                    self.index += 2;
+                    let start = self.index;
+                    let closing = if parent.text.is_empty() {
+                        if self.peek_op() != Some('>') {
+                            return self.unexpected("`>`");
+                        }
+                        Name {
+                            text: String::new(),
+                            span: self.next_span()
+                        }
+                    } else {
+                        self.parse_name(&format!("`{}`", parent.text))?
+                    };
+                    if closing.text != parent.text {
+                        return Err(self.error_at(
+                            start,
+                            closing.span,
+                            format!("expected `</{}>`, found `</{}>`", parent.text, closing.text)
+                        ));
+                    }
+                    self.expect_op('>')?;
+                    return Ok(children);
+                }
+                TokenNode::Group(Delimiter::Brace, ref stream) => {
+                    self.index += 1;
+                    let code = self.parse_group(self.index - 1, stream.clone(), tree.span)?;
+                    if !code.is_empty() {
+                        children.push(Child::Code(code));
+                    }
+                }
+                _ => children.push(Child::Text(self.parse_text()))
+            }
+        }
+    }
+
+    // Text is rebuilt from the positions of its tokens, including whitespace
+    // next to the surrounding tags, then collapsed following the JSX rules.
+    fn parse_text(&mut self) -> String {
+        let start = self.index;
+        while self.peek().map_or(false, is_text) {
+            self.index += 1;
+        }
+
+        if let Some(text) = self.template_text(start) {
+            return collapse_whitespace(&text);
+        }
+
+        let mut writer = match start.checked_sub(1) {
+            Some(previous) => Writer::after(self.trees[previous].span),
+            None => Writer::new()
+        };
+        for tree in &self.trees[start..self.index] {
+            writer.write(tree.clone());
+        }
+        if let Some(next) = self.peek() {
+            writer.pad_to(next.span);
+        }
+
+        collapse_whitespace(&writer.text)
+    }
+
+    fn parse_code(&mut self) -> Result<Code> {
+        let mut code = Code::default();
+        let mut tokens = vec![];
+        let mut after_expression = false;
+
+        while let Some(tree) = self.peek().cloned() {
+            if !after_expression && self.at_element() {
+                let start = self.index;
+                match self.parse_element() {
+                    Ok(element) => {
+                        flush_tokens(&mut code, &mut tokens);
+                        code.parts.push(CodePart::Element(element));
+                        after_expression = true;
+                        continue;
+                    }
+                    Err(error) => {
+                        if !self.at_qualified_path(start) {
+                            return Err(error);
+                        }
+                        self.index = start;
+                    }
+                }
+            }
+
+            after_expression = {
+                let previous = self.index.checked_sub(1).and_then(|index| self.trees.get(index));
+                ends_expression(previous, &tree)
+            };
+            self.index += 1;
+
+            match tree.kind {
+                TokenNode::Group(delimiter, ref stream) => {
+                    flush_tokens(&mut code, &mut tokens);
+                    let inner = self.parse_group(self.index - 1, stream.clone(), tree.span)?;
+                    code.parts.push(CodePart::Group(delimiter, inner));
+                }
+                _ => tokens.push(tree.clone())
+            }
+        }
+
+        flush_tokens(&mut code, &mut tokens);
+        Ok(code)
+    }
+
+    fn at_element(&self) -> bool {
+        let next = self.peek_nth(1);
+        self.peek_op() == Some('<') && (next.and_then(term_of).is_some() || next.and_then(op_of) == Some('>'))
+    }
+
+    // Distinguishes `<T as Trait>::item` and `<T>::item` from elements.
+    fn at_qualified_path(&self, start: usize) -> bool {
+        let mut depth = 0;
+        let mut index = start;
+
+        while let Some(tree) = self.trees.get(index) {
+            index += 1;
+            match op_of(tree) {
+                Some('<') => depth += 1,
+                Some('>') if index < 2 || op_of(&self.trees[index - 2]) != Some('-') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let ops = (self.trees.get(index).and_then(op_of), self.trees.get(index + 1).and_then(op_of));
+        depth == 0 && ops == (Some(':'), Some(':'))
+    }
+}
+
+// The index among all tokens of the tree at the given index, or of the end
+// of the trees, counting from the given index of the first one. Groups count
+// as their delimiters and the tokens between them.
+fn token_index(trees: &[TokenTree], base: usize, index: usize) -> usize {
+    base + trees[..index].iter().map(token_count).sum::<usize>()
+}
+
+fn token_count(tree: &TokenTree) -> usize {
+    let inner = |stream: &TokenStream| stream.clone().into_iter().map(|tree| token_count(&tree)).sum::<usize>();
+    match tree.kind {
+        TokenNode::Group(Delimiter::None, ref stream) => inner(stream),
+        TokenNode::Group(_, ref stream) => inner(stream) + 2,
+        _ => 1
+    }
+}
+
+fn flush_tokens(code: &mut Code, tokens: &mut Vec<TokenTree>) {
+    if !tokens.is_empty() {
+        let stream = tokens.drain(..).collect();
+        code.parts.push(CodePart::Tokens(stream));
+    }
+}
+
+// Lines are trimmed, except for whitespace on the same line as the tags
+// around the text, and blank lines are dropped. What remains is joined
+// with single spaces.
+fn collapse_whitespace(text: &str) -> String {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let last = lines.len().saturating_sub(1);
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(index, &line)| {
+            let line = if index == 0 { line } else { line.trim_left() };
+            if index == last {
+                line
+            } else {
+                line.trim_right()
+            }
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_text(tree: &TokenTree) -> bool {
+    match tree.kind {
+        TokenNode::Op('<', _) | TokenNode::Group(Delimiter::Brace, _) => false,
+        _ => true
+    }
+}
+
+// Whether a `<` right after the given tree would be a comparison or generic
+// arguments, e.g. in `a < b` or `collect::<Vec<_>>()`, rather than an element.
+fn ends_expression(previous: Option<&TokenTree>, tree: &TokenTree) -> bool {
+    match tree.kind {
+        TokenNode::Op(':', _) => previous.and_then(op_of) == Some(':'),
+        TokenNode::Op(op, _) => op == '?',
+        TokenNode::Term(term) => !EXPRESSION_KEYWORDS.contains(&term.as_str()),
+        _ => true
+    }
+}
+
+fn describe(tree: Option<&TokenTree>) -> String {
+    match tree.map(|tree| &tree.kind) {
+        None => "end of input".to_string(),
+        Some(&TokenNode::Group(Delimiter::Brace, _)) => "`{`".to_string(),
+        Some(&TokenNode::Group(Delimiter::Parenthesis, _)) => "`(`".to_string(),
+        Some(&TokenNode::Group(Delimiter::Bracket, _)) => "`[`".to_string(),
+        Some(_) => format!("`{}`", tree.unwrap())
+    }
+}
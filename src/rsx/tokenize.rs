@@ -0,0 +1,351 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use quote::{Ident, ToTokens, Tokens};
+
+use rsx::types::*;
+use source::delimiters;
+use tokens::{placeholder, respan};
+
+impl ToTokens for Element {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        if self.component {
+            return component(self, tokens);
+        }
+        if self.is_fragment() {
+            return nodes(&self.children).to_tokens(tokens);
+        }
+
+        let name = self.name.text.as_str();
+        // Until nodes have a place of their own for it, the key is the first
+        // attribute.
+        let key = self.key.as_ref().map(|key| {
+            quote! {
+                DOMAttribute::from((DOMAttributeName::from("key"), DOMAttributeValue::from(#key)))
+            }
+        });
+        let listeners = &self.listeners;
+        let has_attributes = key.is_some() || !self.attributes.is_empty() || !listeners.is_empty();
+        let attributes = if self.attributes.iter().any(is_dynamic) {
+            dynamic_attributes(key.as_ref(), &self.attributes, listeners)
+        } else {
+            let entries: Vec<Tokens> = key.into_iter()
+                .chain(self.attributes.iter().map(|attribute| quote! { #attribute }))
+                .chain(listeners.iter().map(|listener| quote! { #listener }))
+                .collect();
+            quote! { box [#(#entries),*] }
+        };
+        let children = flatten(&self.children);
+        let children = if children.iter().any(is_spliced) {
+            let nodes = nodes(&self.children);
+            quote! { #nodes.into_boxed_slice() }
+        } else {
+            quote! { box [#(#children),*] }
+        };
+
+        let node = match (has_attributes, self.children.is_empty()) {
+            (false, true) => quote! {
+                DOMNode::from(DOMTagName::from(#name))
+            },
+            (true, true) => quote! {
+                DOMNode::from((DOMTagName::from(#name), #attributes))
+            },
+            (false, false) => quote! {
+                DOMNode::from((DOMTagName::from(#name), #children))
+            },
+            (true, false) => quote! {
+                DOMNode::from((DOMTagName::from(#name), #attributes, #children))
+            }
+        };
+
+        node.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Key {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        match *self {
+            Key::Str(ref key) => key.to_tokens(tokens),
+            Key::Code(ref code) => quote! { { #code }.to_string() }.to_tokens(tokens)
+        }
+    }
+}
+
+// Handlers are attributes whose value is converted into the listener type
+// of the node, the `E` of `DOMNode<S, C, E>`.
+impl ToTokens for Listener {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        let name = self.name.as_str();
+        let code = &self.code;
+
+        let listener = quote! {
+            DOMAttribute::from((DOMAttributeName::from(#name), DOMAttributeValue::EventListener(From::from({ #code }))))
+        };
+
+        listener.to_tokens(tokens);
+    }
+}
+
+// Components are created through the builder of their props, see
+// `#[derive(Props)]`, and then rendered.
+fn component(element: &Element, tokens: &mut Tokens) {
+    let mut path = Tokens::new();
+    path.append(element.name.text.replace('.', "::"));
+
+    // Props are set one at a time, since optional ones are only set when
+    // their value is `Some`. Errors about a prop, e.g. a mistyped value or
+    // an unknown name, point to its attribute.
+    let setters: Vec<Tokens> = element
+        .attributes
+        .iter()
+        .map(|attribute| {
+            let setter = Ident::new(attribute.name.text.as_str());
+            let statement = match attribute.value {
+                AttributeValue::Optional(ref code) => quote! {
+                    let __rsx_props = match { #code } {
+                        Some(value) => __rsx_props.#setter(value),
+                        None => __rsx_props
+                    };
+                },
+                ref value => quote! {
+                    let __rsx_props = __rsx_props.#setter(#value);
+                }
+            };
+            respan(statement, attribute.name.span)
+        })
+        .collect();
+    let children = if element.children.is_empty() {
+        None
+    } else {
+        let children = nodes(&element.children);
+        Some(quote! { let __rsx_props = __rsx_props.children(#children); })
+    };
+
+    // Errors about missing props point to the component.
+    let rendered = quote! {
+        {
+            let __rsx_props = #path::builder();
+            #(#setters)*
+            #children
+            Component::render(&__rsx_props.build())
+        }
+    };
+    respan(rendered, element.name.span).to_tokens(tokens);
+}
+
+// Whether an attribute may stand for any number of attributes, so that they
+// can only be known at runtime.
+fn is_dynamic(attribute: &Attribute) -> bool {
+    match attribute.value {
+        AttributeValue::Optional(_) | AttributeValue::Spread(_) => true,
+        _ => false
+    }
+}
+
+// Attributes are collected in source order, after the key if any and before
+// the listeners, then those followed by another attribute of the same name
+// are dropped, so that the last one wins.
+fn dynamic_attributes(key: Option<&Tokens>, attributes: &[Attribute], listeners: &[Listener]) -> Tokens {
+    let pushes: Vec<Tokens> = attributes
+        .iter()
+        .map(|attribute| match attribute.value {
+            AttributeValue::Optional(ref code) => {
+                let name = attribute.name.text.as_str();
+                quote! {
+                    __rsx_attributes.extend({ #code }.map(|value| {
+                        DOMAttribute::from((DOMAttributeName::from(#name), DOMAttributeValue::from(value)))
+                    }));
+                }
+            }
+            AttributeValue::Spread(ref code) => quote! {
+                __rsx_attributes.extend({ #code });
+            },
+            _ => quote! {
+                __rsx_attributes.push(#attribute);
+            }
+        })
+        .collect();
+
+    let key = key.map(|key| quote! { __rsx_attributes.push(#key); });
+
+    quote! {
+        {
+            let mut __rsx_attributes = Vec::new();
+            #key
+            #(#pushes)*
+            #(__rsx_attributes.push(#listeners);)*
+            let mut __rsx_index = __rsx_attributes.len();
+            while __rsx_index > 0 {
+                __rsx_index -= 1;
+                let __rsx_overridden = __rsx_attributes[__rsx_index + 1..]
+                    .iter()
+                    .any(|attribute| attribute.0 == __rsx_attributes[__rsx_index].0);
+                if __rsx_overridden {
+                    __rsx_attributes.remove(__rsx_index);
+                }
+            }
+            __rsx_attributes.into_boxed_slice()
+        }
+    }
+}
+
+// Children which are fragments are replaced by their own children.
+fn flatten(children: &[Child]) -> Vec<&Child> {
+    let mut flattened = vec![];
+    for child in children {
+        match *child {
+            Child::Element(ref element) if element.is_fragment() => flattened.extend(flatten(&element.children)),
+            _ => flattened.push(child)
+        }
+    }
+    flattened
+}
+
+fn is_spliced(child: &&Child) -> bool {
+    match **child {
+        Child::Code(_) => true,
+        _ => false
+    }
+}
+
+// Evaluates to a `Vec<DOMNode>` of the given children. Code may evaluate to
+// several nodes, which are spliced in, see `splice_helpers`.
+fn nodes(children: &[Child]) -> Tokens {
+    let children = flatten(children);
+    if !children.iter().any(is_spliced) {
+        return quote! { vec![#(#children),*] };
+    }
+
+    let pushes: Vec<Tokens> = children
+        .iter()
+        .map(|child| match **child {
+            Child::Code(ref code) => quote! {
+                __RsxChild(::std::cell::Cell::new(Some({ #code }))).splice(&mut __rsx_nodes);
+            },
+            _ => quote! {
+                __rsx_nodes.push(#child);
+            }
+        })
+        .collect();
+
+    quote! {
+        {
+            let mut __rsx_nodes: Vec<DOMNode> = Vec::new();
+            #(#pushes)*
+            __rsx_nodes
+        }
+    }
+}
+
+// Code among children evaluates either to something convertible into a
+// single node, or to any number of nodes, e.g. a fragment, an iterator or an
+// `Option`. Which `splice` applies is decided by method resolution, which
+// tries the wrapper itself before a reference to it.
+pub fn splice_helpers() -> Tokens {
+    quote! {
+        #[allow(dead_code)]
+        struct __RsxChild<T>(::std::cell::Cell<Option<T>>);
+
+        #[allow(dead_code)]
+        trait __RsxNodes {
+            fn splice(self, nodes: &mut Vec<DOMNode>);
+        }
+
+        impl<T: IntoIterator<Item = DOMNode>> __RsxNodes for __RsxChild<T> {
+            fn splice(self, nodes: &mut Vec<DOMNode>) {
+                nodes.extend(self.0.into_inner().unwrap());
+            }
+        }
+
+        #[allow(dead_code)]
+        trait __RsxNode {
+            fn splice(self, nodes: &mut Vec<DOMNode>);
+        }
+
+        impl<'a, T: Into<DOMNode>> __RsxNode for &'a __RsxChild<T> {
+            fn splice(self, nodes: &mut Vec<DOMNode>) {
+                nodes.push(self.0.take().unwrap().into());
+            }
+        }
+    }
+}
+
+impl ToTokens for Attribute {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        let name = self.name.text.as_str();
+        let value = &self.value;
+
+        let attribute = quote! {
+            DOMAttribute::from((DOMAttributeName::from(#name), DOMAttributeValue::from(#value)))
+        };
+
+        attribute.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for AttributeValue {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        match *self {
+            AttributeValue::Str(ref value) => value.to_tokens(tokens),
+            AttributeValue::Style(ref style) => style.to_tokens(tokens),
+            AttributeValue::Styles(ref parts) => quote! {
+                {
+                    let mut declarations = StyleDeclarations(InlineDeclarations::default());
+                    #(declarations.0.extend(#parts.0);)*
+                    declarations
+                }
+            }.to_tokens(tokens),
+            AttributeValue::Code(ref code) | AttributeValue::Optional(ref code) | AttributeValue::Spread(ref code) => {
+                let code = quote! { { #code } };
+                code.to_tokens(tokens)
+            }
+            AttributeValue::Element(ref element) => element.to_tokens(tokens)
+        }
+    }
+}
+
+impl ToTokens for StylePart {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        match *self {
+            StylePart::Static(ref style) => style.to_tokens(tokens),
+            StylePart::Code(ref code) => quote! { ({ #code }) }.to_tokens(tokens)
+        }
+    }
+}
+
+impl ToTokens for Child {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        let child = match *self {
+            Child::Element(ref element) => quote! { #element },
+            Child::Text(ref text) => quote! { DOMNode::from(#text) },
+            Child::Code(ref code) => quote! { DOMNode::from({ #code }) }
+        };
+
+        child.to_tokens(tokens);
+    }
+}
+
+impl ToTokens for Code {
+    fn to_tokens(&self, tokens: &mut Tokens) {
+        for part in &self.parts {
+            match *part {
+                CodePart::Tokens(ref stream) => placeholder(stream.clone()).to_tokens(tokens),
+                CodePart::Group(delimiter, ref code) => {
+                    let (open, close) = delimiters(delimiter);
+                    tokens.append(open);
+                    code.to_tokens(tokens);
+                    tokens.append(close);
+                }
+                CodePart::Element(ref element) => element.to_tokens(tokens)
+            }
+        }
+    }
+}
@@ -0,0 +1,114 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use proc_macro::{Delimiter, Span, TokenStream};
+
+use css::InlineStyle;
+
+#[derive(Debug)]
+pub struct Element {
+    pub name: Name,
+    pub attributes: Vec<Attribute>,
+    pub children: Vec<Child>,
+    // The `key` attribute, which identifies the element among its siblings.
+    // It's kept apart from the other attributes to be checked.
+    pub key: Option<Key>,
+    // Event handlers, which come after the other attributes.
+    pub listeners: Vec<Listener>,
+    // Whether the name is a type implementing `Component`, rendered with
+    // the attributes as props, rather than a tag name.
+    pub component: bool
+}
+
+#[derive(Debug)]
+pub struct Name {
+    pub text: String,
+    pub span: Span
+}
+
+#[derive(Debug)]
+pub struct Attribute {
+    pub name: Name,
+    pub value: AttributeValue
+}
+
+#[derive(Debug)]
+pub enum AttributeValue {
+    Str(String),
+    Style(InlineStyle),
+    Styles(Vec<StylePart>),
+    Code(Code),
+    // Code evaluating to an `Option`, written `{...}?`. The attribute is
+    // left out when it's `None`.
+    Optional(Code),
+    Element(Element),
+    // Attributes spread into an element, with `{..attributes}`, where the
+    // code evaluates to any iterator of attributes. The name is always `..`.
+    Spread(Code)
+}
+
+#[derive(Debug)]
+pub enum Key {
+    Str(String),
+    // Code converted with `to_string`.
+    Code(Code)
+}
+
+// An event handler, written as an attribute named after its event, e.g.
+// `onClick={|event| ...}` for the `click` event.
+#[derive(Debug)]
+pub struct Listener {
+    pub name: String,
+    pub code: Code
+}
+
+// Declarations merged into a single `style` attribute, in order, from
+// `class` and `style` attributes.
+#[derive(Debug)]
+pub enum StylePart {
+    Static(InlineStyle),
+    Code(Code)
+}
+
+#[derive(Debug)]
+pub enum Child {
+    Element(Element),
+    Text(String),
+    Code(Code)
+}
+
+// Rust code found inside `{ ... }` blocks. Elements nested anywhere in the
+// code are parsed as well, so that they can be expanded in place.
+#[derive(Debug, Default)]
+pub struct Code {
+    pub parts: Vec<CodePart>
+}
+
+#[derive(Debug)]
+pub enum CodePart {
+    Tokens(TokenStream),
+    Group(Delimiter, Code),
+    Element(Element)
+}
+
+impl Element {
+    // Fragments, `<>...</>`, are elements without a name, which stand for
+    // their children.
+    pub fn is_fragment(&self) -> bool {
+        self.name.text.is_empty()
+    }
+}
+
+impl Code {
+    pub fn is_empty(&self) -> bool {
+        self.parts.is_empty()
+    }
+}
@@ -0,0 +1,56 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use errors::Result;
+use rsx::types::*;
+
+// Calls the given function on every element of a tree, children first,
+// including elements nested inside attribute values and code blocks.
+pub fn visit_elements<F>(element: &mut Element, f: &mut F) -> Result<()>
+where
+    F: FnMut(&mut Element) -> Result<()>
+{
+    for attribute in &mut element.attributes {
+        match attribute.value {
+            AttributeValue::Element(ref mut element) => visit_elements(element, f)?,
+            AttributeValue::Code(ref mut code)
+            | AttributeValue::Optional(ref mut code)
+            | AttributeValue::Spread(ref mut code) => visit_code(code, f)?,
+            _ => {}
+        }
+    }
+    for listener in &mut element.listeners {
+        visit_code(&mut listener.code, f)?;
+    }
+    for child in &mut element.children {
+        match *child {
+            Child::Element(ref mut element) => visit_elements(element, f)?,
+            Child::Code(ref mut code) => visit_code(code, f)?,
+            Child::Text(_) => {}
+        }
+    }
+
+    f(element)
+}
+
+fn visit_code<F>(code: &mut Code, f: &mut F) -> Result<()>
+where
+    F: FnMut(&mut Element) -> Result<()>
+{
+    for part in &mut code.parts {
+        match *part {
+            CodePart::Element(ref mut element) => visit_elements(element, f)?,
+            CodePart::Group(_, ref mut code) => visit_code(code, f)?,
+            CodePart::Tokens(_) => {}
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,334 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use proc_macro::{Delimiter, LineColumn, Span, TokenNode, TokenStream, TokenTree};
+
+use errors::{Error, Result};
+use files;
+
+// Text handed to a parser, remembering where each part of it came from so
+// that diagnostics can point back at the macro input or at an external file.
+pub struct Source {
+    pub text: String,
+    origin: Origin,
+    groups: Vec<Group>
+}
+
+// A brace delimited group written into the text, with the text range it
+// covers, delimiters included.
+pub struct Group {
+    pub start: usize,
+    pub end: usize,
+    pub stream: TokenStream
+}
+
+enum Origin {
+    // The start offset of every token written into the text, with its span.
+    Tokens(Vec<(usize, Span)>),
+    // The file the text was read from, and the span of the literal naming it.
+    File(PathBuf, Span)
+}
+
+impl Source {
+    pub fn from_tokens(input: TokenStream) -> Self {
+        let mut writer = Writer::new();
+        writer.write_stream(input);
+
+        Source {
+            text: writer.text,
+            origin: Origin::Tokens(writer.offsets),
+            groups: writer.groups
+        }
+    }
+
+    pub fn from_file(path: PathBuf, span: Span) -> Result<Self> {
+        let mut text = String::new();
+        File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|error| {
+                Error::new(span, format!("Couldn't read file {}: {}", files::display(&path), error))
+            })?;
+
+        Ok(Source {
+            text,
+            origin: Origin::File(path, span),
+            groups: vec![]
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        match self.origin {
+            Origin::File(ref path, _) => Some(path),
+            Origin::Tokens(_) => None
+        }
+    }
+
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    pub fn error<S>(&self, offset: usize, message: S) -> Error
+    where
+        S: Into<String>
+    {
+        let (span, message) = self.locate(offset, message.into());
+        Error::new(span, message)
+    }
+
+    pub fn warning<S>(&self, offset: usize, message: S)
+    where
+        S: Into<String>
+    {
+        let (span, message) = self.locate(offset, message.into());
+        span.warning(message).emit();
+    }
+
+    // The span closest to the given offset. For external files, that's the
+    // span of whatever named the file.
+    pub fn span(&self, offset: usize) -> Span {
+        match self.origin {
+            Origin::Tokens(ref offsets) => offsets
+                .iter()
+                .rev()
+                .find(|&&(start, _)| start <= offset)
+                .map_or_else(Span::call_site, |&(_, span)| span),
+            Origin::File(_, span) => span
+        }
+    }
+
+    // Spans can't point inside external files, so for those the position is
+    // spelled out in the message instead.
+    fn locate(&self, offset: usize, message: String) -> (Span, String) {
+        let span = self.span(offset);
+        match self.origin {
+            Origin::Tokens(_) => (span, message),
+            Origin::File(ref path, _) => {
+                let (line, column) = line_column(&self.text, offset);
+                (span, format!("{}:{}:{}: {}", files::display(path), line, column, message))
+            }
+        }
+    }
+}
+
+pub fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |line| line.chars().count()) + 1;
+    (line, column)
+}
+
+// The start and end offset in the given text of every token of the stream
+// parsed from it, delimiters included, in order.
+pub fn token_offsets(text: &str, input: TokenStream) -> Vec<(usize, usize)> {
+    let mut offsets = vec![];
+    let mut position = 0;
+    collect_offsets(text, input, &mut position, &mut offsets);
+    offsets
+}
+
+fn collect_offsets(text: &str, input: TokenStream, position: &mut usize, offsets: &mut Vec<(usize, usize)>) {
+    for tree in input {
+        match tree.kind {
+            TokenNode::Group(Delimiter::None, stream) => collect_offsets(text, stream, position, offsets),
+            TokenNode::Group(delimiter, stream) => {
+                let (open, close) = delimiters(delimiter);
+                offsets.push(find_token(text, position, open));
+                collect_offsets(text, stream, position, offsets);
+                offsets.push(find_token(text, position, close));
+            }
+            TokenNode::Term(term) => offsets.push(find_token(text, position, term.as_str())),
+            TokenNode::Op(op, _) => offsets.push(find_token(text, position, &op.to_string())),
+            TokenNode::Literal(literal) => offsets.push(find_token(text, position, &literal.to_string()))
+        }
+    }
+}
+
+// Skips whitespace and comments up to the given token, then the token itself.
+// Literals written differently than they're printed, e.g. with other escapes,
+// are assumed to end at the next whitespace.
+fn find_token(text: &str, position: &mut usize, token: &str) -> (usize, usize) {
+    loop {
+        let rest = &text[*position..];
+        let trimmed = rest.trim_left();
+        *position += rest.len() - trimmed.len();
+        if trimmed.starts_with("//") {
+            *position += trimmed.find('\n').unwrap_or_else(|| trimmed.len());
+        } else if trimmed.starts_with("/*") {
+            *position += trimmed.find("*/").map_or(trimmed.len(), |end| end + 2);
+        } else {
+            break;
+        }
+    }
+
+    let start = *position;
+    let rest = &text[start..];
+    *position += if rest.starts_with(token) {
+        token.len()
+    } else {
+        rest.find(char::is_whitespace).unwrap_or_else(|| rest.len())
+    };
+    (start, *position)
+}
+
+// Writes tokens out as text, recreating the whitespace which originally
+// separated them from their spans. Converting a `TokenStream` to a `String`
+// instead puts a space between every two tokens, which for CSS turns ".foo"
+// into ". foo" and "margin-left" into "margin - left".
+pub struct Writer {
+    pub text: String,
+    offsets: Vec<(usize, Span)>,
+    groups: Vec<Group>,
+    end: Option<Position>
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer {
+            text: String::new(),
+            offsets: vec![],
+            groups: vec![],
+            end: None
+        }
+    }
+
+    // Starts writing right after the given token, so that any whitespace
+    // between the two is written out as well.
+    pub fn after(span: Span) -> Self {
+        Writer {
+            end: Some(end(span)),
+            ..Writer::new()
+        }
+    }
+
+    pub fn write_stream(&mut self, input: TokenStream) {
+        for tree in input {
+            self.write(tree);
+        }
+    }
+
+    pub fn write(&mut self, tree: TokenTree) {
+        let span = tree.span;
+        let (start, end) = (start(span), end(span));
+
+        match tree.kind {
+            TokenNode::Group(Delimiter::None, stream) => self.write_stream(stream),
+            TokenNode::Group(delimiter, stream) => {
+                let (open, close) = delimiters(delimiter);
+                self.write_str(open, span, start, next_column(start));
+                // Brace groups may be interpolated code, which is kept around
+                // to be spliced into the expansion.
+                let group = match delimiter {
+                    Delimiter::Brace => Some((self.text.len() - open.len(), stream.clone())),
+                    _ => None
+                };
+                self.write_stream(stream);
+                self.write_str(close, span, previous_column(end), end);
+                if let Some((group_start, stream)) = group {
+                    self.groups.push(Group {
+                        start: group_start,
+                        end: self.text.len(),
+                        stream
+                    });
+                }
+            }
+            TokenNode::Term(term) => self.write_str(term.as_str(), span, start, end),
+            TokenNode::Op(op, _) => self.write_str(&op.to_string(), span, start, end),
+            TokenNode::Literal(literal) => self.write_str(&literal.to_string(), span, start, end)
+        }
+    }
+
+    // Writes the whitespace between the last written token and the given one.
+    pub fn pad_to(&mut self, span: Span) {
+        let start = start(span);
+        if let Some(whitespace) = self.end.and_then(|end| whitespace_between(end, start)) {
+            self.text.push_str(&whitespace);
+        }
+    }
+
+    fn write_str(&mut self, text: &str, span: Span, start: Position, end: Position) {
+        if let Some(previous) = self.end {
+            // Tokens without a sensible position, e.g. created by another
+            // macro, get separated like `TokenStream::to_string` would.
+            let positioned = start.line != end.line || start.column != end.column;
+            let whitespace = match whitespace_between(previous, start) {
+                Some(ref whitespace) if positioned => whitespace.clone(),
+                _ => " ".to_string()
+            };
+            self.text.push_str(&whitespace);
+        }
+        self.offsets.push((self.text.len(), span));
+        self.text.push_str(text);
+        self.end = Some(end);
+    }
+}
+
+fn whitespace_between(end: Position, start: Position) -> Option<String> {
+    if start.line > end.line {
+        let newlines = "\n".repeat(start.line - end.line);
+        Some(newlines + &" ".repeat(start.column))
+    } else if start.line == end.line && start.column >= end.column {
+        Some(" ".repeat(start.column - end.column))
+    } else {
+        None
+    }
+}
+
+fn next_column(position: Position) -> Position {
+    Position {
+        line: position.line,
+        column: position.column + 1
+    }
+}
+
+fn previous_column(position: Position) -> Position {
+    Position {
+        line: position.line,
+        column: position.column.saturating_sub(1)
+    }
+}
+
+pub fn delimiters(delimiter: Delimiter) -> (&'static str, &'static str) {
+    match delimiter {
+        Delimiter::Parenthesis => ("(", ")"),
+        Delimiter::Brace => ("{", "}"),
+        Delimiter::Bracket => ("[", "]"),
+        Delimiter::None => ("", "")
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Position {
+    line: usize,
+    column: usize
+}
+
+fn start(span: Span) -> Position {
+    position(span.start())
+}
+
+fn end(span: Span) -> Position {
+    position(span.end())
+}
+
+// The fields of `LineColumn` aren't public yet, so they're read back from its
+// `Debug` output, which looks like `LineColumn { line: 1, column: 4 }`.
+fn position(point: LineColumn) -> Position {
+    let debug = format!("{:?}", point);
+    let mut numbers = debug.split(|c: char| !c.is_digit(10)).filter(|number| !number.is_empty());
+    let mut next = || numbers.next().and_then(|number| number.parse().ok()).unwrap_or(0);
+    let line = next();
+    let column = next();
+    Position { line, column }
+}
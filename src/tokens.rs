@@ -0,0 +1,97 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::cell::RefCell;
+use std::mem;
+
+use proc_macro::{Span, TokenNode, TokenStream, TokenTree};
+use quote::Tokens;
+
+// Prefix of the identifiers standing in for streams, see `placeholder`.
+const PLACEHOLDER: &str = "__rsx_placeholder_";
+
+thread_local! {
+    static STREAMS: RefCell<Vec<TokenStream>> = RefCell::new(vec![]);
+}
+
+pub fn op_of(tree: &TokenTree) -> Option<char> {
+    match tree.kind {
+        TokenNode::Op(op, _) => Some(op),
+        _ => None
+    }
+}
+
+pub fn term_of(tree: &TokenTree) -> Option<String> {
+    match tree.kind {
+        TokenNode::Term(term) => Some(term.as_str().to_string()),
+        _ => None
+    }
+}
+
+// Expansions are built with `quote!`, which works with text, so parsing them
+// leaves every token pointing to the whole macro invocation. Streams which
+// should keep their spans, e.g. code from the input, are set aside instead,
+// with an identifier in their place until they're put back by `restore`.
+pub fn placeholder(stream: TokenStream) -> Tokens {
+    STREAMS.with(|streams| {
+        let mut streams = streams.borrow_mut();
+        streams.push(stream);
+        let mut tokens = Tokens::new();
+        tokens.append(format!("{}{}", PLACEHOLDER, streams.len() - 1));
+        tokens
+    })
+}
+
+// Same as `placeholder`, for generated tokens which should all point to the
+// given span, e.g. the name of the element they're generated for.
+pub fn respan(tokens: Tokens, span: Span) -> Tokens {
+    placeholder(set_span(tokens.parse().unwrap(), span))
+}
+
+// Parses an expansion, putting back every stream set aside for it. The rest
+// is rebuilt pointing to the macro invocation, as it would be unchanged, so
+// that it can still refer to local variables of the caller.
+pub fn restore(tokens: Tokens) -> TokenStream {
+    let streams = STREAMS.with(|streams| mem::replace(&mut *streams.borrow_mut(), vec![]));
+    replace_placeholders(tokens.parse().unwrap(), &streams, Some(Span::call_site()))
+}
+
+fn replace_placeholders(stream: TokenStream, streams: &[TokenStream], span: Option<Span>) -> TokenStream {
+    let mut trees = vec![];
+    for tree in stream {
+        let tree_span = span.unwrap_or(tree.span);
+        match tree.kind {
+            TokenNode::Term(term) if term.as_str().starts_with(PLACEHOLDER) => {
+                let index: usize = term.as_str()[PLACEHOLDER.len()..].parse().unwrap();
+                trees.extend(replace_placeholders(streams[index].clone(), streams, None));
+            }
+            TokenNode::Group(delimiter, inner) => trees.push(TokenTree {
+                span: tree_span,
+                kind: TokenNode::Group(delimiter, replace_placeholders(inner, streams, span))
+            }),
+            kind => trees.push(TokenTree { span: tree_span, kind })
+        }
+    }
+    trees.into_iter().collect()
+}
+
+fn set_span(stream: TokenStream, span: Span) -> TokenStream {
+    stream
+        .into_iter()
+        .map(|tree| {
+            let kind = match tree.kind {
+                TokenNode::Group(delimiter, inner) => TokenNode::Group(delimiter, set_span(inner, span)),
+                kind => kind
+            };
+            TokenTree { span, kind }
+        })
+        .collect()
+}
@@ -0,0 +1,73 @@
+/*
+Copyright 2016 Mozilla
+Licensed under the Apache License, Version 2.0 (the "License"); you may not use
+this file except in compliance with the License. You may obtain a copy of the
+License at http://www.apache.org/licenses/LICENSE-2.0
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the
+specific language governing permissions and limitations under the License.
+*/
+
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Compiles every file in `tests/fixtures/diagnostics` against the macros, and
+// checks that rustc reports each of the comment lines at the top of the file,
+// e.g. `// error: unexpected end of input`, as many times as it's listed.
+#[test]
+fn test_diagnostics() {
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    let macros = find_macros(&deps);
+    let out_dir = env::temp_dir().join("rsx-diagnostics");
+    fs::create_dir_all(&out_dir).unwrap();
+
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/diagnostics");
+    let mut paths: Vec<PathBuf> = fs::read_dir(fixtures).unwrap().map(|entry| entry.unwrap().path()).collect();
+    paths.sort();
+
+    let mut failures = vec![];
+    for path in paths {
+        let mut source = String::new();
+        File::open(&path).unwrap().read_to_string(&mut source).unwrap();
+        let expected: Vec<&str> = source.lines().take_while(|line| line.starts_with("// ")).map(|line| &line[3..]).collect();
+
+        let output = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+            .args(&["--crate-type", "lib", "--emit", "metadata", "--out-dir"])
+            .arg(&out_dir)
+            .arg("-L")
+            .arg(&deps)
+            .arg("--extern")
+            .arg(format!("rsx={}", macros.display()))
+            .arg(&path)
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        for diagnostic in &expected {
+            let count = expected.iter().filter(|other| other == &diagnostic).count();
+            if stderr.matches(diagnostic).count() < count {
+                failures.push(format!("{}: expected `{}` in:\n{}", path.display(), diagnostic, stderr));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+// The most recently built macros library, next to this test's executable.
+fn find_macros(deps: &Path) -> PathBuf {
+    let prefix = format!("{}rsx-", env::consts::DLL_PREFIX);
+    fs::read_dir(deps)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with(&prefix) && name.ends_with(env::consts::DLL_SUFFIX)
+        })
+        .max_by_key(|path| fs::metadata(path).unwrap().modified().unwrap())
+        .expect("the macros library should be built before the tests")
+}
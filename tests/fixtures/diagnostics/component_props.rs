@@ -0,0 +1,63 @@
+// error[E0277]: the trait bound `GreetingMissingName: GreetingBuilderProp<std::string::String>` is not satisfied
+// error[E0599]: no method named `colour` found for type `GreetingBuilder<std::string::String>`
+// error[E0277]: the trait bound `u32: std::convert::From<&str>` is not satisfied
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::{rsx, Props};
+
+pub struct DOMTagName(&'static str);
+
+impl From<&'static str> for DOMTagName {
+    fn from(name: &'static str) -> Self {
+        DOMTagName(name)
+    }
+}
+
+pub struct DOMNode;
+
+impl From<DOMTagName> for DOMNode {
+    fn from(_: DOMTagName) -> Self {
+        DOMNode
+    }
+}
+
+pub trait Component {
+    fn render(&self) -> DOMNode;
+}
+
+#[derive(Props)]
+pub struct Greeting {
+    name: String,
+    #[prop(default)]
+    count: u32
+}
+
+impl Component for Greeting {
+    fn render(&self) -> DOMNode {
+        rsx! { <view /> }
+    }
+}
+
+pub fn missing() -> DOMNode {
+    rsx! {
+        components;
+        <Greeting count={1u8} />
+    }
+}
+
+pub fn unknown() -> DOMNode {
+    rsx! {
+        components;
+        <Greeting name="world" colour="red" />
+    }
+}
+
+pub fn mistyped() -> DOMNode {
+    rsx! {
+        components;
+        <Greeting name="world" count="many" />
+    }
+}
@@ -0,0 +1,19 @@
+// error[E0599]: no method named `titel` found
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+extern crate rsx_stylesheet;
+
+use rsx::css;
+use rsx_stylesheet::types::*;
+
+pub fn undefined_accessor() {
+    let mut styles = css! {
+        accessors;
+        .title {
+            width: 10px;
+        }
+    };
+    styles.titel();
+}
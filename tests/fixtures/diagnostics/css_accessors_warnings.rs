@@ -0,0 +1,38 @@
+// warning: method is never used: `unused`
+// warning: method is never used: `unused_too`
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+extern crate rsx_stylesheet;
+
+use rsx::css;
+use rsx_stylesheet::types::*;
+
+pub fn unused_accessor() {
+    let mut styles = css! {
+        accessors;
+        .used {
+            width: 10px;
+        }
+        .unused {
+            height: 10px;
+        }
+    };
+    styles.used();
+}
+
+css! {
+    type = Styles;
+    accessors;
+    .used {
+        width: 10px;
+    }
+    .unused-too {
+        height: 10px;
+    }
+}
+
+pub fn unused_accessor_of_private_type() {
+    Styles::new().used();
+}
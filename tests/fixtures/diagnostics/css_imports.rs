@@ -0,0 +1,11 @@
+// error: tests/fixtures/import_cycle_b.css:12:1: import cycle: tests/fixtures/import_cycle_a.css -> tests/fixtures/import_cycle_b.css -> tests/fixtures/import_cycle_a.css
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::css;
+
+pub fn import_cycle() {
+    css!("tests/fixtures/import_cycle_a.css");
+}
@@ -0,0 +1,44 @@
+// error: unknown property `flex-dirction`, did you mean `flex-direction`?
+// error: invalid value `sideways` for property `flex-direction`
+// error: unknown or unsupported property `frobnicate`
+// error: expected `:` after `color red`
+// error: invalid selector `.foo:nonsense`
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::css;
+
+pub fn unknown_properties() {
+    css! {
+        .foo {
+            flex-dirction: row;
+            frobnicate: 1;
+        }
+    };
+}
+
+pub fn invalid_value() {
+    css! {
+        .foo {
+            flex-direction: sideways;
+        }
+    };
+}
+
+pub fn syntax_error() {
+    css! {
+        .foo {
+            color red;
+        }
+    };
+}
+
+pub fn invalid_selector() {
+    css! {
+        .foo:nonsense {
+            width: 1px;
+        }
+    };
+}
@@ -0,0 +1,18 @@
+// warning: unknown property `flex-dirction`, did you mean `flex-direction`?
+// warning: unknown or unsupported property `frobnicate`
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::css;
+
+pub fn unknown_properties() {
+    css! {
+        lint = warn;
+        .foo {
+            flex-dirction: row;
+            frobnicate: 1;
+        }
+    };
+}
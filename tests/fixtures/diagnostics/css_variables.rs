@@ -0,0 +1,28 @@
+// error: undefined custom property `--missing`
+// error: custom property `--a` depends on itself: --a -> --b -> --a
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::css;
+
+pub fn undefined() {
+    css! {
+        .foo {
+            width: var(--missing);
+        }
+    };
+}
+
+pub fn cycle() {
+    css! {
+        :root {
+            --a: var(--b);
+            --b: var(--a);
+        }
+        .foo {
+            width: var(--a);
+        }
+    };
+}
@@ -0,0 +1,27 @@
+// error: expected a closure, e.g. `onClick={|event| ...}`
+// error: expected a closure, e.g. `onClick={|event| ...}`
+// error: expected a closure, e.g. `onMouseDown={|event| ...}`
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::rsx;
+
+pub fn shorthand() {
+    rsx! {
+        <view onClick />
+    };
+}
+
+pub fn string_literal() {
+    rsx! {
+        <view onClick="alert()" />
+    };
+}
+
+pub fn literal() {
+    rsx! {
+        <view onMouseDown={42} />
+    };
+}
@@ -0,0 +1,13 @@
+// error: expected `#[prop(default)]` or `#[prop(default = "...")]` on `size`
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::Props;
+
+#[derive(Props)]
+pub struct Image {
+    #[prop(fallback = "0")]
+    size: u32
+}
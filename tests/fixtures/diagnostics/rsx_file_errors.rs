@@ -0,0 +1,11 @@
+// error: tests/fixtures/malformed.rsx:13:25: expected `</text>`, found `</view>`
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::rsx_file;
+
+pub fn malformed() {
+    rsx_file!("tests/fixtures/malformed.rsx");
+}
@@ -0,0 +1,42 @@
+// error: expected `</view>`, found end of input
+// error: expected `</view>`, found `</text>`
+// error: expected a string literal, `{...}` or an element, found `/`
+// error: expected end of input, found `<`
+// error: expected an element name, found `42`
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::rsx;
+
+pub fn unclosed() {
+    rsx! {
+        <view>
+    };
+}
+
+pub fn mismatched() {
+    rsx! {
+        <view></text>
+    };
+}
+
+pub fn missing_value() {
+    rsx! {
+        <view title= />
+    };
+}
+
+pub fn siblings() {
+    rsx! {
+        <view />
+        <text />
+    };
+}
+
+pub fn not_a_name() {
+    rsx! {
+        <42 />
+    };
+}
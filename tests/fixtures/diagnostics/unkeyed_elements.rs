@@ -0,0 +1,15 @@
+// warning: elements created in a loop should have a `key` attribute
+
+#![feature(proc_macro)]
+
+extern crate rsx;
+
+use rsx::rsx;
+
+pub fn render(names: &[&str]) {
+    rsx! {
+        <view>
+            { names.iter().map(|name| <text>{ *name }</text>) }
+        </view>
+    };
+}
@@ -19,6 +19,8 @@ extern crate rsx_stylesheet;
 
 use rsx::css;
 use rsx::rsx;
+use rsx::rsx_file;
+use rsx::Props;
 use rsx_dom::types::*;
 use rsx_stylesheet::types::*;
 
@@ -107,6 +109,388 @@ fn test_rsx_to_dom() {
     assert_eq!(node, expected);
 }
 
+#[test]
+fn test_rsx_text() {
+    let node = rsx! {
+        <text>
+            <b>a</b>, <i>b</i>!
+            Hello   world, again!
+        </text>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::Text),
+        box [
+            DOMNode::from((DOMTagName::from("b"), box [DOMNode::from("a")])),
+            DOMNode::from(", "),
+            DOMNode::from((DOMTagName::from("i"), box [DOMNode::from("b")])),
+            DOMNode::from("! Hello   world, again!"),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_text_whitespace() {
+    let node = rsx! {
+        <text>
+            <b>a</b> <i>b</i>
+            <b>c</b>{ "d" }  { "e" }
+        </text>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::Text),
+        box [
+            DOMNode::from((DOMTagName::from("b"), box [DOMNode::from("a")])),
+            DOMNode::from(" "),
+            DOMNode::from((DOMTagName::from("i"), box [DOMNode::from("b")])),
+            DOMNode::from((DOMTagName::from("b"), box [DOMNode::from("c")])),
+            DOMNode::from("d"),
+            DOMNode::from("  "),
+            DOMNode::from("e"),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_inline_style() {
+    let node = rsx! {
+        <view style="width: 10px; flex-grow: 1">
+            Hello world!
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMAttribute::from((
+                DOMAttributeName::from(KnownAttributeName::Style),
+                DOMAttributeValue::from(StyleDeclarations(InlineDeclarations::from_vec(vec![
+                    StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+                    StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+                ])))
+            )),
+        ],
+        box [DOMNode::from("Hello world!")]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+trait Component {
+    fn render(&self) -> DOMNode;
+}
+
+#[derive(Props)]
+struct Greeting {
+    name: String,
+    #[prop(default = "\"!\".to_string()")]
+    punctuation: String,
+    #[prop(default)]
+    count: u32,
+    subtitle: Option<String>,
+    children: Vec<DOMNode>
+}
+
+impl Component for Greeting {
+    fn render(&self) -> DOMNode {
+        let greeting = format!(
+            "Hello {}, with {} child{} {} {:?}",
+            self.name,
+            self.children.len(),
+            self.punctuation,
+            self.count,
+            self.subtitle
+        );
+        rsx! {
+            <text>{ greeting }</text>
+        }
+    }
+}
+
+#[test]
+fn test_rsx_components() {
+    let node = rsx! {
+        components;
+        <view>
+            <Greeting name="world" count={1u8}>
+                <image />
+            </Greeting>
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMNode::from(String::from("Hello world, with 1 child! 1 None"))]
+            )),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_components_optional_props() {
+    let subtitle = Some("Hi");
+    let count: Option<u32> = None;
+    let node = rsx! {
+        components;
+        <Greeting name="world" subtitle={subtitle}? count={count}?>
+            <image />
+        </Greeting>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::Text),
+        box [DOMNode::from(String::from("Hello world, with 1 child! 0 Some(\"Hi\")"))]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_fragments() {
+    let texts = rsx! {
+        <>
+            <text>Hello</text>
+            <text>world</text>
+        </>
+    };
+    let node = rsx! {
+        <view>
+            <>
+                <image />
+            </>
+            { texts }
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMNode::from(DOMTagName::from(KnownElementName::Image)),
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMNode::from("Hello")]
+            )),
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMNode::from("world")]
+            )),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+// Listeners are converted into the event type of the node, which these
+// stand in for, since closures can't be compared.
+#[derive(Clone, Debug, PartialEq)]
+struct TestListener;
+
+impl<F: Fn(&str)> From<F> for TestListener {
+    fn from(_: F) -> Self {
+        TestListener
+    }
+}
+
+#[test]
+fn test_rsx_splicing() {
+    let names = vec!["Alice", "Bob"];
+    let badge: Option<DOMNode> = None;
+    let node = rsx! {
+        <view>
+            { names.iter().map(|name| <text key={name}>{ *name }</text>) }
+            { badge }
+            { Some(<image />) }
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMAttribute::from((DOMAttributeName::from("key"), DOMAttributeValue::from(String::from("Alice"))))],
+                box [DOMNode::from("Alice")]
+            )),
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMAttribute::from((DOMAttributeName::from("key"), DOMAttributeValue::from(String::from("Bob"))))],
+                box [DOMNode::from("Bob")]
+            )),
+            DOMNode::from(DOMTagName::from(KnownElementName::Image)),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_splicing_turbofish() {
+    let names = vec!["Alice", "Bob"];
+    let node = rsx! {
+        <view>
+            { names.iter().map(|name| <text key={name}>{ *name }</text>).collect::<Vec<_>>() }
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMAttribute::from((DOMAttributeName::from("key"), DOMAttributeValue::from(String::from("Alice"))))],
+                box [DOMNode::from("Alice")]
+            )),
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMAttribute::from((DOMAttributeName::from("key"), DOMAttributeValue::from(String::from("Bob"))))],
+                box [DOMNode::from("Bob")]
+            )),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_spread_attributes() {
+    let attributes = vec![
+        DOMAttribute::from((DOMAttributeName::from("src"), DOMAttributeValue::from("a.png"))),
+        DOMAttribute::from((DOMAttributeName::from("alt"), DOMAttributeValue::from("A"))),
+    ];
+    let node = rsx! {
+        <image src="b.png" {..attributes} alt="B" />
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::Image),
+        box [
+            DOMAttribute::from((DOMAttributeName::from(KnownAttributeName::Src), DOMAttributeValue::from("a.png"))),
+            DOMAttribute::from((DOMAttributeName::from("alt"), DOMAttributeValue::from("B"))),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_optional_attributes() {
+    let title: Option<&str> = None;
+    let alt = Some("Logo");
+    let node = rsx! {
+        <image title={title}? alt={alt}? hidden />
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::Image),
+        box [
+            DOMAttribute::from((DOMAttributeName::from("alt"), DOMAttributeValue::from("Logo"))),
+            DOMAttribute::from((DOMAttributeName::from("hidden"), DOMAttributeValue::from(true))),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_file() {
+    let name = "world";
+    let node = rsx_file!("tests/fixtures/template.rsx");
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMAttribute::from((
+                DOMAttributeName::from(KnownAttributeName::Style),
+                DOMAttributeValue::from(StyleDeclarations(InlineDeclarations::from_vec(vec![
+                    StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+                ])))
+            )),
+        ],
+        box [
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMNode::from("Hello "), DOMNode::from("world"), DOMNode::from("!")]
+            )),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_keys() {
+    let id = 2;
+    let node = rsx! {
+        <view>
+            <text key="first">Hello</text>
+            <text key={id}>world</text>
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMAttribute::from((DOMAttributeName::from("key"), DOMAttributeValue::from("first")))],
+                box [DOMNode::from("Hello")]
+            )),
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [DOMAttribute::from((DOMAttributeName::from("key"), DOMAttributeValue::from(String::from("2"))))],
+                box [DOMNode::from("world")]
+            )),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_event_listeners() {
+    type DOMNode = rsx_dom::types::DOMNode<StyleDeclarations, ComputedStyles, TestListener>;
+
+    let node: DOMNode = rsx! {
+        <view onMouseDown={|_| ()}>
+            <text onClick={|event| println!("{}", event)}>Hello</text>
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMAttribute::from((
+                DOMAttributeName::from("onMouseDown"),
+                DOMAttributeValue::EventListener(TestListener)
+            )),
+        ],
+        box [
+            DOMNode::from((
+                DOMTagName::from(KnownElementName::Text),
+                box [
+                    DOMAttribute::from((
+                        DOMAttributeName::from("onClick"),
+                        DOMAttributeValue::EventListener(TestListener)
+                    )),
+                ],
+                box [DOMNode::from("Hello")]
+            )),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
 #[test]
 fn test_css_to_stylesheet_1() {
     let stylesheet = css! {
@@ -320,38 +704,547 @@ fn test_css_to_stylesheet_4() {
 }
 
 #[test]
-fn test_rsx_and_css_1() {
-    let mut stylesheet = css! {
+fn test_css_to_stylesheet_5() {
+    let stylesheet = css! {
+        lint = allow;
         .foo {
-            margin: 0 auto;
+            flex-dirction: row;
             padding: 10px;
         }
     };
 
-    let node = rsx! {
-        <div style={stylesheet.get(".foo")}>
-            Hello world!
-        </div>
-    };
-
-    let expected = DOMNode::from((
-        DOMTagName::from(KnownElementName::Div),
-        box [
-            DOMAttribute::from((
-                DOMAttributeName::from(KnownAttributeName::Style),
-                DOMAttributeValue::from(StyleDeclarations(InlineDeclarations::from_vec(vec![
-                    StyleDeclaration::Layout(FlexStyle::MarginTop(StyleUnit::Point(0.0.into()))),
-                    StyleDeclaration::Layout(FlexStyle::MarginRight(StyleUnit::Auto)),
-                    StyleDeclaration::Layout(FlexStyle::MarginBottom(StyleUnit::Point(0.0.into()))),
-                    StyleDeclaration::Layout(FlexStyle::MarginLeft(StyleUnit::Auto)),
-                    StyleDeclaration::Layout(FlexStyle::PaddingTop(StyleUnit::Point(10.0.into()))),
+    let expected = Stylesheet::from(InlineRules::from_vec(vec![
+        StyleRule {
+            selectors: StyleSelectors(InlineSelectors::from_vec(vec![
+                StyleSelector {
+                    css_string: ".foo".into(),
+                    specificity: 1024u32
+                },
+            ])),
+            declarations: StyleDeclarations(InlineDeclarations::from_vec(vec![
+                StyleDeclaration::Layout(FlexStyle::PaddingTop(StyleUnit::Point(10f32.into()))),
+                StyleDeclaration::Layout(FlexStyle::PaddingRight(StyleUnit::Point(10f32.into()))),
+                StyleDeclaration::Layout(FlexStyle::PaddingBottom(StyleUnit::Point(10f32.into()))),
+                StyleDeclaration::Layout(FlexStyle::PaddingLeft(StyleUnit::Point(10f32.into()))),
+            ]))
+        },
+    ]));
+
+    assert_eq!(stylesheet, expected);
+
+    stylesheet.ignore_unused();
+    expected.ignore_unused();
+}
+
+#[test]
+fn test_css_to_stylesheet_6() {
+    let stylesheet = css! {
+        .bar-baz-qux {
+            flex-direction: row-reverse;
+        }
+        #a1 > .b {
+            margin-left: -10px;
+        }
+        .c:hover, .d::before {
+            width: 10px !important;
+        }
+        .e[data-x="y"] {
+            align-self: flex-end;
+        }
+    };
+
+    let expected = Stylesheet::from(InlineRules::from_vec(vec![
+        StyleRule {
+            selectors: StyleSelectors(InlineSelectors::from_vec(vec![
+                StyleSelector {
+                    css_string: ".bar-baz-qux".into(),
+                    specificity: 1024u32
+                },
+            ])),
+            declarations: StyleDeclarations(InlineDeclarations::from_vec(vec![
+                StyleDeclaration::Layout(FlexStyle::FlexDirection(FlexDirection::RowReverse)),
+            ]))
+        },
+        StyleRule {
+            selectors: StyleSelectors(InlineSelectors::from_vec(vec![
+                StyleSelector {
+                    css_string: "#a1 > .b".into(),
+                    specificity: 1049600u32
+                },
+            ])),
+            declarations: StyleDeclarations(InlineDeclarations::from_vec(vec![
+                StyleDeclaration::Layout(FlexStyle::MarginLeft(StyleUnit::Point((-10f32).into()))),
+            ]))
+        },
+        StyleRule {
+            selectors: StyleSelectors(InlineSelectors::from_vec(vec![
+                StyleSelector {
+                    css_string: ".c:hover".into(),
+                    specificity: 2048u32
+                },
+                StyleSelector {
+                    css_string: ".d::before".into(),
+                    specificity: 1025u32
+                },
+            ])),
+            declarations: StyleDeclarations(InlineDeclarations::from_vec(vec![
+                StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+            ]))
+        },
+        StyleRule {
+            selectors: StyleSelectors(InlineSelectors::from_vec(vec![
+                StyleSelector {
+                    css_string: ".e[data-x=\"y\"]".into(),
+                    specificity: 2048u32
+                },
+            ])),
+            declarations: StyleDeclarations(InlineDeclarations::from_vec(vec![
+                StyleDeclaration::Layout(FlexStyle::AlignSelf(Align::FlexEnd)),
+            ]))
+        },
+    ]));
+
+    assert_eq!(stylesheet, expected);
+
+    stylesheet.ignore_unused();
+    expected.ignore_unused();
+}
+
+#[test]
+fn test_css_to_stylesheet_7() {
+    let stylesheet = css!(relative_to = file; "fixtures/test_2.css");
+    let expected = css!("tests/fixtures/test_2.css");
+
+    assert_eq!(stylesheet, expected);
+
+    stylesheet.ignore_unused();
+    expected.ignore_unused();
+}
+
+#[test]
+fn test_css_to_stylesheet_8() {
+    let stylesheet = css!("tests/fixtures/test_3.css");
+    let expected = css! {
+        .foo {
+            margin: 0 auto;
+            padding: 10px;
+        }
+        .bar {
+            padding: 5px;
+        }
+    };
+
+    assert_eq!(stylesheet, expected);
+
+    stylesheet.ignore_unused();
+    expected.ignore_unused();
+}
+
+#[test]
+fn test_css_to_stylesheet_9() {
+    let stylesheet = css! {
+        @import "tests/fixtures/test_2.css";
+    };
+    let expected = css!("tests/fixtures/test_2.css");
+
+    assert_eq!(stylesheet, expected);
+
+    stylesheet.ignore_unused();
+    expected.ignore_unused();
+}
+
+#[test]
+fn test_css_to_stylesheet_10() {
+    let stylesheet = css! {
+        :root {
+            --gap: 12px;
+            --direction: row;
+            --padding: var(--gap);
+        }
+        .foo {
+            padding: var(--padding);
+            flex-direction: var(--missing, var(--direction));
+            margin-left: var(--missing, 5px);
+        }
+    };
+    let expected = css! {
+        .foo {
+            padding: 12px;
+            flex-direction: row;
+            margin-left: 5px;
+        }
+    };
+
+    assert_eq!(stylesheet, expected);
+
+    stylesheet.ignore_unused();
+    expected.ignore_unused();
+}
+
+#[test]
+fn test_css_to_stylesheet_11() {
+    let width = 50;
+    let opacity = 0.5;
+
+    let stylesheet = css! {
+        .foo {
+            padding: 10px;
+            width: {width * 2}px;
+        }
+        .bar {
+            opacity: {opacity};
+        }
+    };
+
+    let expected = Stylesheet::from(InlineRules::from_vec(vec![
+        StyleRule {
+            selectors: StyleSelectors(InlineSelectors::from_vec(vec![
+                StyleSelector {
+                    css_string: ".foo".into(),
+                    specificity: 1024u32
+                },
+            ])),
+            declarations: StyleDeclarations(InlineDeclarations::from_vec(vec![
+                StyleDeclaration::Layout(FlexStyle::PaddingTop(StyleUnit::Point(10f32.into()))),
+                StyleDeclaration::Layout(FlexStyle::PaddingRight(StyleUnit::Point(10f32.into()))),
+                StyleDeclaration::Layout(FlexStyle::PaddingBottom(StyleUnit::Point(10f32.into()))),
+                StyleDeclaration::Layout(FlexStyle::PaddingLeft(StyleUnit::Point(10f32.into()))),
+                StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+            ]))
+        },
+        StyleRule {
+            selectors: StyleSelectors(InlineSelectors::from_vec(vec![
+                StyleSelector {
+                    css_string: ".bar".into(),
+                    specificity: 1024u32
+                },
+            ])),
+            declarations: StyleDeclarations(InlineDeclarations::from_vec(vec![
+                StyleDeclaration::Theme(ThemeStyle::Opacity(0.5.into())),
+            ]))
+        },
+    ]));
+
+    assert_eq!(stylesheet, expected);
+
+    stylesheet.ignore_unused();
+    expected.ignore_unused();
+}
+
+#[test]
+fn test_css_scoped() {
+    let (mut stylesheet, classes) = css! {
+        scoped;
+        .foo {
+            width: 10px;
+        }
+        .bar-baz {
+            height: 20px;
+        }
+    };
+
+    assert!(classes.foo.starts_with("foo_"));
+    assert!(classes.bar_baz.starts_with("bar-baz_"));
+    assert_eq!(&classes.foo[4..], &classes.bar_baz[8..]);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+    ]));
+    assert_eq!(stylesheet.get(&format!(".{}", classes.foo)), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+    ]));
+    assert_eq!(stylesheet.get(&format!(".{}", classes.bar_baz)), expected);
+
+    stylesheet.ignore_unused();
+}
+
+#[test]
+fn test_css_accessors() {
+    let mut styles = css! {
+        accessors;
+        .foo, .bar-baz {
+            width: 10px;
+        }
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+    ]));
+    assert_eq!(styles.foo(), expected);
+    assert_eq!(styles.bar_baz(), expected);
+
+    styles.into_stylesheet().ignore_unused();
+}
+
+#[test]
+fn test_css_states() {
+    let mut styles = css! {
+        states;
+        .button {
+            width: 10px;
+        }
+        .button:hover {
+            height: 20px;
+        }
+        .button:hover:active {
+            flex-grow: 1;
+        }
+        .button:focus {
+            flex-grow: 2;
+        }
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+    ]));
+    assert_eq!(styles.get_for_state(".button", &["hover"]), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+    ]));
+    assert_eq!(styles.get_for_state(".button", &["active", "hover"]), expected);
+
+    styles.into_stylesheet().ignore_unused();
+}
+
+css! {
+    type = pub ButtonStyles;
+    states;
+    .button {
+        width: 10px;
+    }
+    .button:hover {
+        height: 20px;
+    }
+}
+
+#[test]
+fn test_css_named() {
+    struct Button {
+        styles: ButtonStyles
+    }
+
+    let mut button = Button {
+        styles: ButtonStyles::new()
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+    ]));
+    assert_eq!(button.styles.get_for_state(".button", &["hover"]), expected);
+
+    button.styles.into_stylesheet().ignore_unused();
+}
+
+#[test]
+fn test_css_media() {
+    let mut styles = css! {
+        media;
+        .column {
+            width: 100px;
+        }
+        @media (min-width: 600px) {
+            .column {
+                height: 20px;
+            }
+        }
+        @media screen and (orientation: landscape), (min-resolution: 2dppx) {
+            .column {
+                flex-grow: 1;
+            }
+        }
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+    ]));
+    assert_eq!(styles.get_for_viewport(".column", 320.0, 480.0, 1.0, "light"), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+    ]));
+    assert_eq!(styles.get_for_viewport(".column", 800.0, 600.0, 1.0, "light"), expected);
+
+    styles.into_stylesheet().ignore_unused();
+}
+
+#[test]
+fn test_css_media_interpolated() {
+    let height = 20;
+    let mut styles = css! {
+        media;
+        .column {
+            width: 100px;
+        }
+        @media (min-width: 600px) {
+            .column {
+                height: {height}px;
+                flex-grow: 1;
+            }
+        }
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+    ]));
+    assert_eq!(styles.get_for_viewport(".column", 800.0, 600.0, 1.0, "light"), expected);
+
+    styles.into_stylesheet().ignore_unused();
+}
+
+#[test]
+fn test_css_themes() {
+    let mut styles = css! {
+        themes;
+        .card {
+            width: 100px;
+        }
+        @media (prefers-color-scheme: dark) {
+            .card {
+                height: 20px;
+            }
+        }
+        @theme contrast {
+            .card {
+                flex-grow: 1;
+            }
+        }
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+    ]));
+    assert_eq!(styles.get_for_theme(".card", "light"), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+    ]));
+    assert_eq!(styles.get_for_theme(".card", "dark"), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+    ]));
+    assert_eq!(styles.get_for_theme(".card", "contrast"), expected);
+
+    styles.into_stylesheet().ignore_unused();
+}
+
+#[test]
+fn test_css_themes_and_states() {
+    let mut styles = css! {
+        themes;
+        states;
+        .card {
+            width: 100px;
+        }
+        .card:hover {
+            flex-grow: 1;
+        }
+        @theme dark {
+            .card {
+                height: 20px;
+            }
+        }
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+    ]));
+    assert_eq!(styles.get_for_state(".card", &["hover"]), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(100f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+    ]));
+    assert_eq!(styles.get_for_theme(".card", "dark"), expected);
+
+    styles.into_stylesheet().ignore_unused();
+}
+
+#[test]
+fn test_css_matching() {
+    let mut styles = css! {
+        matching;
+        .card .title {
+            height: 20px;
+        }
+        .title {
+            width: 10px;
+        }
+        .list > .item {
+            flex-grow: 1;
+        }
+        .item + .item {
+            flex-grow: 2;
+        }
+    };
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+        StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+    ]));
+    assert_eq!(styles.get_for_element(&["view.card", "view", "text.title"]), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+    ]));
+    assert_eq!(styles.get_for_element(&["view", "text.title"]), expected);
+
+    let expected = StyleDeclarations(InlineDeclarations::from_vec(vec![
+        StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+    ]));
+    assert_eq!(styles.get_for_element(&["view.list", "view.item"]), expected);
+    assert_eq!(styles.get_for_element(&["view.list", "view", "view.item"]), StyleDeclarations(InlineDeclarations::default()));
+
+    styles.into_stylesheet().ignore_unused();
+}
+
+#[test]
+fn test_rsx_and_css_1() {
+    let mut stylesheet = css! {
+        .foo {
+            margin: 0 auto;
+            padding: 10px;
+        }
+    };
+
+    let node = rsx! {
+        <div style={stylesheet.get(".foo")}>
+            Hello world!
+        </div>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::Div),
+        box [
+            DOMAttribute::from((
+                DOMAttributeName::from(KnownAttributeName::Style),
+                DOMAttributeValue::from(StyleDeclarations(InlineDeclarations::from_vec(vec![
+                    StyleDeclaration::Layout(FlexStyle::MarginTop(StyleUnit::Point(0.0.into()))),
+                    StyleDeclaration::Layout(FlexStyle::MarginRight(StyleUnit::Auto)),
+                    StyleDeclaration::Layout(FlexStyle::MarginBottom(StyleUnit::Point(0.0.into()))),
+                    StyleDeclaration::Layout(FlexStyle::MarginLeft(StyleUnit::Auto)),
+                    StyleDeclaration::Layout(FlexStyle::PaddingTop(StyleUnit::Point(10.0.into()))),
                     StyleDeclaration::Layout(FlexStyle::PaddingRight(StyleUnit::Point(10.0.into()))),
                     StyleDeclaration::Layout(FlexStyle::PaddingBottom(StyleUnit::Point(10f32.into()))),
                     StyleDeclaration::Layout(FlexStyle::PaddingLeft(StyleUnit::Point(10.0.into()))),
                 ])))
             )),
         ],
-        box [DOMNode::from("Hello world !")]
+        box [DOMNode::from("Hello world!")]
     ));
 
     assert_eq!(node, expected);
@@ -375,7 +1268,7 @@ fn test_rsx_and_css_2() {
                 DOMAttributeValue::from(StyleDeclarations(InlineDeclarations::default()))
             )),
         ],
-        box [DOMNode::from("Hello world !")]
+        box [DOMNode::from("Hello world!")]
     ));
 
     assert_eq!(node, expected);
@@ -383,6 +1276,66 @@ fn test_rsx_and_css_2() {
     stylesheet.ignore_unused();
 }
 
+#[test]
+fn test_rsx_and_css_3() {
+    let node = rsx! {
+        stylesheet = "tests/fixtures/test_4.css";
+        <view class="bar foo">
+            Hello world!
+        </view>
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMAttribute::from((
+                DOMAttributeName::from(KnownAttributeName::Style),
+                DOMAttributeValue::from(StyleDeclarations(InlineDeclarations::from_vec(vec![
+                    StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(10f32.into()))),
+                    StyleDeclaration::Layout(FlexStyle::Height(StyleUnit::Point(20f32.into()))),
+                ])))
+            )),
+        ],
+        box [DOMNode::from("Hello world!")]
+    ));
+
+    assert_eq!(node, expected);
+}
+
+#[test]
+fn test_rsx_and_css_4() {
+    let node = rsx! {
+        stylesheet = "tests/fixtures/test_1.css";
+        <view class="image" style="flex-grow: 1" />
+    };
+
+    let expected = DOMNode::from((
+        DOMTagName::from(KnownElementName::View),
+        box [
+            DOMAttribute::from((
+                DOMAttributeName::from(KnownAttributeName::Style),
+                DOMAttributeValue::from(StyleDeclarations(InlineDeclarations::from_vec(vec![
+                    StyleDeclaration::Theme(ThemeStyle::BackgroundColor(Color {
+                        components: ColorComponents {
+                            red: 0,
+                            green: 128,
+                            blue: 0,
+                            alpha: 255
+                        },
+                        authored: Some("green".into())
+                    })),
+                    StyleDeclaration::Theme(ThemeStyle::Opacity(0.5.into())),
+                    StyleDeclaration::Layout(FlexStyle::Width(StyleUnit::Point(80.0.into()))),
+                    StyleDeclaration::Layout(FlexStyle::MarginRight(StyleUnit::Point(20.0.into()))),
+                    StyleDeclaration::Layout(FlexStyle::FlexGrow(1.0.into())),
+                ])))
+            )),
+        ]
+    ));
+
+    assert_eq!(node, expected);
+}
+
 #[test]
 fn test_example_1() {
     let mut stylesheet = css! {
@@ -457,7 +1410,7 @@ fn test_example_1() {
                         ])))
                     )),
                 ],
-                box [DOMNode::from("Hello world !")]
+                box [DOMNode::from("Hello world!")]
             )),
         ]
     ));